@@ -0,0 +1,144 @@
+use crate::state::StateFile;
+use crate::test::results::{BookInfo, Results};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+
+/// How much of a book each test covers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Chunk {
+    Words(usize),
+    Paragraph,
+}
+
+/// A long text typed a chunk at a time, across as many runs as it takes.
+#[derive(Debug)]
+pub struct Book {
+    /// Words of the text, with paragraph ends marked by a trailing newline.
+    words: Vec<String>,
+    /// Hex-encoded hash of the words, identifying the text in bookmarks.
+    hash: String,
+    /// Index of the first word not yet typed.
+    position: usize,
+    /// Number of words in the chunk being typed.
+    served: usize,
+}
+
+impl Book {
+    /// Open a text at its bookmark, or at the start if it hasn't been read before.
+    pub fn new(words: Vec<String>, bookmarks: &Bookmarks) -> Self {
+        let mut hasher = Sha256::new();
+        for word in &words {
+            hasher.update(word);
+            hasher.update([0]);
+        }
+        let hash = format!("{:x}", hasher.finalize());
+
+        let position = bookmarks
+            .positions
+            .get(&hash)
+            .copied()
+            .filter(|&position| position < words.len())
+            .unwrap_or(0);
+
+        Self {
+            words,
+            hash,
+            position,
+            served: 0,
+        }
+    }
+
+    pub fn hash(&self) -> &str {
+        &self.hash
+    }
+
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    pub fn word_count(&self) -> usize {
+        self.words.len()
+    }
+
+    /// The words following the bookmark, which stays put until they've been typed.
+    pub fn next_chunk(&mut self, chunk: Chunk) -> Vec<String> {
+        let rest = &self.words[self.position..];
+        self.served = match chunk {
+            Chunk::Words(count) => count.min(rest.len()),
+            Chunk::Paragraph => rest
+                .iter()
+                .position(|word| word.ends_with('\n'))
+                .map_or(rest.len(), |end| end + 1),
+        };
+        rest[..self.served].to_vec()
+    }
+
+    /// Move the bookmark past the chunk if the test was finished, starting over at the end.
+    pub fn record(&mut self, results: &Results) -> BookInfo {
+        let mut finished = false;
+        if results.complete {
+            self.position += self.served;
+            if self.position >= self.words.len() {
+                self.position = 0;
+                finished = true;
+            }
+        }
+
+        BookInfo {
+            position: self.position,
+            total: self.words.len(),
+            finished,
+            save_error: None,
+        }
+    }
+}
+
+/// Positions reached in each book, saved between runs.
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct Bookmarks {
+    #[serde(default)]
+    pub positions: BTreeMap<String, usize>,
+}
+
+impl StateFile for Bookmarks {
+    const NAME: &'static str = "bookmarks";
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words(text: &str) -> Vec<String> {
+        crate::generate::prose::tokenize(text, false, Default::default())
+    }
+
+    #[test]
+    fn serves_chunks() {
+        let mut book = Book::new(words("one two three\n\nfour five"), &Bookmarks::default());
+        assert_eq!(book.next_chunk(Chunk::Words(2)), vec!["one", "two"]);
+        assert_eq!(
+            book.next_chunk(Chunk::Paragraph),
+            vec!["one", "two", "three\n"]
+        );
+
+        book.position = 3;
+        assert_eq!(book.next_chunk(Chunk::Words(5)), vec!["four", "five"]);
+        assert_eq!(book.next_chunk(Chunk::Paragraph), vec!["four", "five"]);
+    }
+
+    #[test]
+    fn resumes_from_bookmark() {
+        let text = words("one two three four");
+        let mut bookmarks = Bookmarks::default();
+        let book = Book::new(text.clone(), &bookmarks);
+        assert_eq!(book.position(), 0);
+
+        bookmarks.positions.insert(book.hash().to_owned(), 2);
+        assert_eq!(Book::new(text.clone(), &bookmarks).position(), 2);
+
+        // a different text has its own bookmark
+        assert_eq!(Book::new(words("one two"), &bookmarks).position(), 0);
+    }
+}
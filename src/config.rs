@@ -3,11 +3,33 @@ use serde::{
     Deserialize,
 };
 use ratatui::style::{Color, Modifier, Style};
+use std::collections::BTreeMap;
+
+use crate::generate::{command::Generator, prose::ProseOptions, symbols};
+use crate::keys::KeyBindings;
+use crate::test::matching::MatchPolicy;
 
 #[derive(Debug, Deserialize)]
 #[serde(default)]
 pub struct Config {
     pub default_language: String,
+    /// Seconds without input before a test pauses itself, or 0 to never pause.
+    pub idle_timeout: u64,
+    /// Whether to pause tests while the terminal is unfocused.
+    pub pause_on_focus_loss: bool,
+    pub matching: MatchPolicy,
+    /// Keyboard layout being typed with.
+    pub layout: String,
+    /// Layout the system is set to, when it differs from `layout` and keys should be remapped.
+    pub remap_from: Option<String>,
+    /// Whether to show a keyboard with hints for the next key during tests.
+    pub show_keyboard: bool,
+    pub prose: ProseOptions,
+    /// How often each kind of token appears in symbol drills.
+    pub symbols: symbols::Weights,
+    /// Commands whose output can be typed, by name.
+    pub generators: BTreeMap<String, Generator>,
+    pub keys: KeyBindings,
     pub theme: Theme,
 }
 
@@ -15,6 +37,16 @@ impl Default for Config {
     fn default() -> Self {
         Self {
             default_language: "english200".into(),
+            idle_timeout: 10,
+            pause_on_focus_loss: true,
+            matching: MatchPolicy::default(),
+            layout: "qwerty".into(),
+            remap_from: None,
+            show_keyboard: false,
+            prose: ProseOptions::default(),
+            symbols: symbols::Weights::default(),
+            generators: BTreeMap::new(),
+            keys: KeyBindings::default(),
             theme: Theme::default(),
         }
     }
@@ -33,6 +65,8 @@ pub struct Theme {
     pub input_border: Style,
     #[serde(deserialize_with = "deserialize_style")]
     pub prompt_border: Style,
+    #[serde(deserialize_with = "deserialize_style")]
+    pub input_progress: Style,
 
     #[serde(deserialize_with = "deserialize_style")]
     pub prompt_correct: Style,
@@ -51,23 +85,60 @@ pub struct Theme {
     #[serde(deserialize_with = "deserialize_style")]
     pub prompt_cursor: Style,
 
+    #[serde(deserialize_with = "deserialize_style")]
+    pub keyboard_border: Style,
+    #[serde(deserialize_with = "deserialize_style")]
+    pub keyboard_key: Style,
+    #[serde(deserialize_with = "deserialize_style")]
+    pub keyboard_next: Style,
+    #[serde(deserialize_with = "deserialize_style")]
+    pub keyboard_finger: Style,
+    #[serde(deserialize_with = "deserialize_style")]
+    pub keyboard_hint: Style,
+
+    #[serde(deserialize_with = "deserialize_style")]
+    pub paused_overlay: Style,
+    #[serde(deserialize_with = "deserialize_style")]
+    pub paused_overlay_border: Style,
+
     // results widget
     #[serde(deserialize_with = "deserialize_style")]
     pub results_overview: Style,
     #[serde(deserialize_with = "deserialize_style")]
     pub results_overview_border: Style,
 
+    #[serde(deserialize_with = "deserialize_style")]
+    pub results_flagged: Style,
+
     #[serde(deserialize_with = "deserialize_style")]
     pub results_worst_keys: Style,
     #[serde(deserialize_with = "deserialize_style")]
     pub results_worst_keys_border: Style,
 
+    #[serde(deserialize_with = "deserialize_style")]
+    pub results_fingers: Style,
+    #[serde(deserialize_with = "deserialize_style")]
+    pub results_fingers_border: Style,
+
+    #[serde(deserialize_with = "deserialize_style")]
+    pub results_heatmap_border: Style,
+    #[serde(deserialize_with = "deserialize_style")]
+    pub results_heatmap_untyped: Style,
+    #[serde(deserialize_with = "deserialize_style")]
+    pub results_heatmap_good: Style,
+    #[serde(deserialize_with = "deserialize_style")]
+    pub results_heatmap_fair: Style,
+    #[serde(deserialize_with = "deserialize_style")]
+    pub results_heatmap_poor: Style,
+
     #[serde(deserialize_with = "deserialize_style")]
     pub results_chart: Style,
     #[serde(deserialize_with = "deserialize_style")]
     pub results_chart_x: Style,
     #[serde(deserialize_with = "deserialize_style")]
     pub results_chart_y: Style,
+    #[serde(deserialize_with = "deserialize_style")]
+    pub results_chart_pause: Style,
 
     #[serde(deserialize_with = "deserialize_style")]
     pub results_restart_prompt: Style,
@@ -84,6 +155,7 @@ impl Default for Theme {
 
             input_border: Style::default().fg(Color::Cyan),
             prompt_border: Style::default().fg(Color::Green),
+            input_progress: Style::default().fg(Color::Gray),
 
             prompt_correct: Style::default().fg(Color::Green),
             prompt_incorrect: Style::default().fg(Color::Red),
@@ -99,21 +171,53 @@ impl Default for Theme {
 
             prompt_cursor: Style::default().add_modifier(Modifier::UNDERLINED),
 
+            keyboard_border: Style::default().fg(Color::Magenta),
+            keyboard_key: Style::default().fg(Color::Gray),
+            keyboard_next: Style::default()
+                .fg(Color::Black)
+                .bg(Color::Magenta)
+                .add_modifier(Modifier::BOLD),
+            keyboard_finger: Style::default().fg(Color::Magenta),
+            keyboard_hint: Style::default()
+                .fg(Color::Gray)
+                .add_modifier(Modifier::ITALIC),
+
+            paused_overlay: Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+            paused_overlay_border: Style::default().fg(Color::Yellow),
+
             results_overview: Style::default()
                 .fg(Color::Cyan)
                 .add_modifier(Modifier::BOLD),
             results_overview_border: Style::default().fg(Color::Cyan),
 
+            results_flagged: Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+
             results_worst_keys: Style::default()
                 .fg(Color::Cyan)
                 .add_modifier(Modifier::BOLD),
             results_worst_keys_border: Style::default().fg(Color::Cyan),
 
+            results_fingers: Style::default()
+                .fg(Color::Cyan)
+                .add_modifier(Modifier::BOLD),
+            results_fingers_border: Style::default().fg(Color::Cyan),
+
+            results_heatmap_border: Style::default().fg(Color::Cyan),
+            results_heatmap_untyped: Style::default().fg(Color::DarkGray),
+            results_heatmap_good: Style::default().fg(Color::Black).bg(Color::Green),
+            results_heatmap_fair: Style::default().fg(Color::Black).bg(Color::Yellow),
+            results_heatmap_poor: Style::default().fg(Color::Black).bg(Color::Red),
+
             results_chart: Style::default().fg(Color::Cyan),
             results_chart_x: Style::default().fg(Color::Cyan),
             results_chart_y: Style::default()
                 .fg(Color::Gray)
                 .add_modifier(Modifier::BOLD),
+            results_chart_pause: Style::default().fg(Color::Yellow),
 
             results_restart_prompt: Style::default()
                 .fg(Color::Gray)
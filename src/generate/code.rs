@@ -0,0 +1,338 @@
+use rand::{seq::SliceRandom, Rng};
+use std::{
+    fs, io,
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+// Directories never searched for source files, besides hidden ones
+const IGNORED_DIRS: [&str; 2] = ["target", "node_modules"];
+// Spaces each tab is expanded to
+const TAB_WIDTH: usize = 4;
+
+/// How snippets are taken from source files.
+#[derive(Debug, Clone, Copy)]
+pub struct Options {
+    /// Most lines a snippet may have, not counting blank ones.
+    pub lines: usize,
+    /// Whether to remove comments from languages recognized by file extension.
+    pub strip_comments: bool,
+}
+
+/// How comments and strings are written in a language.
+#[derive(Debug, PartialEq, Eq)]
+struct Syntax {
+    line_comments: &'static [&'static str],
+    block_comment: Option<(&'static str, &'static str)>,
+    /// Characters delimiting strings, inside which comment markers don't count.
+    quotes: &'static [char],
+    /// Whether single quotes start character literals like `'"'` as well as lifetimes.
+    char_literals: bool,
+}
+
+const C_LIKE: Syntax = Syntax {
+    line_comments: &["//"],
+    block_comment: Some(("/*", "*/")),
+    quotes: &['"', '\'', '`'],
+    char_literals: false,
+};
+// Rust is C-like, except that lifetimes leave single quotes unmatched
+const RUST: Syntax = Syntax {
+    line_comments: &["//"],
+    block_comment: Some(("/*", "*/")),
+    quotes: &['"'],
+    char_literals: true,
+};
+const HASH: Syntax = Syntax {
+    line_comments: &["#"],
+    block_comment: None,
+    quotes: &['"', '\''],
+    char_literals: false,
+};
+const DASH: Syntax = Syntax {
+    line_comments: &["--"],
+    block_comment: None,
+    quotes: &['"', '\''],
+    char_literals: false,
+};
+
+/// Guess the language of a source file from its extension.
+fn syntax(path: &Path) -> Option<&'static Syntax> {
+    let extension = path.extension()?.to_str()?;
+    match extension {
+        "rs" => Some(&RUST),
+        "c" | "h" | "cc" | "cpp" | "hpp" | "cs" | "dart" | "go" | "java" | "js" | "jsx" | "kt"
+        | "php" | "scala" | "swift" | "ts" | "tsx" => Some(&C_LIKE),
+        "bash" | "ex" | "exs" | "nix" | "pl" | "py" | "r" | "rb" | "sh" | "toml" | "yaml"
+        | "yml" | "zsh" => Some(&HASH),
+        "elm" | "hs" | "lua" | "sql" => Some(&DASH),
+        _ => None,
+    }
+}
+
+/// Find the source files at some paths, searching directories for files of known languages.
+pub fn source_files(paths: &[PathBuf]) -> io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for path in paths {
+        if path.is_dir() {
+            walk(path, &mut files)?;
+        } else {
+            // files named outright are used whatever their language
+            files.push(path.clone());
+        }
+    }
+    Ok(files)
+}
+
+fn walk(dir: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    let mut entries: Vec<PathBuf> = dir
+        .read_dir()?
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .collect();
+    // sorted, so that a seed picks the same snippet every time
+    entries.sort();
+
+    for path in entries {
+        let name = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or("");
+        if name.starts_with('.') {
+            continue;
+        }
+        if path.is_dir() {
+            if !IGNORED_DIRS.contains(&name) {
+                walk(&path, files)?;
+            }
+        } else if syntax(&path).is_some() {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Pick a random block of code from the files, one line per word with line ends marked by a
+/// trailing newline.
+pub fn snippet(
+    files: &[PathBuf],
+    options: Options,
+    rng: &mut impl Rng,
+) -> Result<Vec<String>, String> {
+    let mut order: Vec<&PathBuf> = files.iter().collect();
+    order.shuffle(rng);
+
+    for path in order {
+        // binary and unreadable files have no code to type
+        let Ok(source) = fs::read_to_string(path) else {
+            continue;
+        };
+        let source = match syntax(path) {
+            Some(syntax) if options.strip_comments => strip_comments(&source, syntax),
+            _ => source,
+        };
+        let lines: Vec<String> = source
+            .lines()
+            .map(|line| {
+                line.replace('\t', &" ".repeat(TAB_WIDTH))
+                    .trim_end()
+                    .to_owned()
+            })
+            .collect();
+
+        if let Some(range) = choose_block(&lines, options.lines, rng) {
+            return Ok(dedent(&lines[range]));
+        }
+    }
+    Err("There is no code to type in the given files.".into())
+}
+
+/// Bytes of spaces and tabs starting a line, leaving other whitespace alone so that the line
+/// can be sliced there.
+fn indent(line: &str) -> usize {
+    line.len() - line.trim_start_matches([' ', '\t']).len()
+}
+
+/// Choose a random block of at most `max_lines` lines which aren't blank, such as a function or
+/// loop, falling back to any run of lines if there are no such blocks.
+fn choose_block(lines: &[String], max_lines: usize, rng: &mut impl Rng) -> Option<Range<usize>> {
+    let code: Vec<usize> = (0..lines.len()).filter(|&i| !lines[i].is_empty()).collect();
+    if code.is_empty() || max_lines == 0 {
+        return None;
+    }
+
+    let blocks: Vec<Range<usize>> = (0..code.len())
+        .filter_map(|start| {
+            let level = indent(&lines[code[start]]);
+            // a block is a line followed by more indented ones, and its closing line if any
+            let mut end = start + 1;
+            while end < code.len() && indent(&lines[code[end]]) > level {
+                end += 1;
+            }
+            if end < code.len() && indent(&lines[code[end]]) == level {
+                let line = lines[code[end]].trim_start();
+                if line.starts_with(['}', ')', ']']) || line == "end" {
+                    end += 1;
+                }
+            }
+            (end - start > 1 && end - start <= max_lines).then_some(start..end)
+        })
+        .collect();
+
+    let range = match blocks.choose(rng) {
+        Some(block) => block.clone(),
+        None => {
+            let start = rng.gen_range(0..code.len());
+            start..(start + max_lines).min(code.len())
+        }
+    };
+    Some(code[range.start]..code[range.end - 1] + 1)
+}
+
+/// Remove blank lines and the indentation common to all lines, marking the end of each line.
+fn dedent(lines: &[String]) -> Vec<String> {
+    let lines: Vec<&String> = lines.iter().filter(|line| !line.is_empty()).collect();
+    let common = lines.iter().map(|line| indent(line)).min().unwrap_or(0);
+
+    let mut words: Vec<String> = lines
+        .iter()
+        .map(|line| line[common..].to_owned() + "\n")
+        .collect();
+    if let Some(last) = words.last_mut() {
+        last.pop();
+    }
+    words
+}
+
+/// Remove comments from source code, leaving comment markers inside strings alone.
+fn strip_comments(source: &str, syntax: &Syntax) -> String {
+    let mut stripped = String::with_capacity(source.len());
+    let mut quote: Option<char> = None;
+    let mut rest = source;
+
+    while let Some(c) = rest.chars().next() {
+        if let Some(open) = quote {
+            // escaped characters never end a string
+            if c == '\\' {
+                let escaped: String = rest.chars().take(2).collect();
+                stripped.push_str(&escaped);
+                rest = &rest[escaped.len()..];
+                continue;
+            }
+            if c == open {
+                quote = None;
+            }
+        } else if let Some(literal) = char_literal(rest).filter(|_| syntax.char_literals) {
+            stripped.push_str(literal);
+            rest = &rest[literal.len()..];
+            continue;
+        } else if syntax.quotes.contains(&c) {
+            quote = Some(c);
+        } else if syntax.line_comments.iter().any(|m| rest.starts_with(m)) {
+            rest = &rest[rest.find('\n').unwrap_or(rest.len())..];
+            continue;
+        } else if let Some((open, close)) = syntax.block_comment {
+            if let Some(comment) = rest.strip_prefix(open) {
+                rest = comment
+                    .find(close)
+                    .map_or("", |end| &comment[end + close.len()..]);
+                continue;
+            }
+        }
+        stripped.push(c);
+        rest = &rest[c.len_utf8()..];
+    }
+    stripped
+}
+
+/// The character literal starting some source, like `'"'` or `'\''`, if it isn't a lifetime.
+fn char_literal(source: &str) -> Option<&str> {
+    let rest = source.strip_prefix('\'')?;
+    let mut chars = rest.char_indices();
+    let end = match chars.next()? {
+        // escapes end at the first quote after the escaped character
+        (_, '\\') => {
+            let (i, escaped) = chars.next()?;
+            let start = i + escaped.len_utf8();
+            start + rest[start..].find('\'')?
+        }
+        _ => match chars.next()? {
+            (i, '\'') => i,
+            _ => return None,
+        },
+    };
+    Some(&source[..end + 2])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    fn lines(source: &str) -> Vec<String> {
+        source.lines().map(ToOwned::to_owned).collect()
+    }
+
+    #[test]
+    fn strips_comments() {
+        let source = "let url = \"https://example.com\"; // a comment\n\
+                      /* a block\n\
+                      comment */ let x = '/';\n\
+                      /// docs\n\
+                      fn f<'a>() {}";
+        assert_eq!(
+            strip_comments(source, &RUST),
+            "let url = \"https://example.com\"; \n let x = '/';\n\nfn f<'a>() {}"
+        );
+        assert_eq!(strip_comments("x = '#' # comment\n", &HASH), "x = '#' \n");
+
+        // quotes in character literals don't start strings
+        let source = "let q = '\"'; // quote\nlet e = '\\''; // escaped\n";
+        assert_eq!(
+            strip_comments(source, &RUST),
+            "let q = '\"'; \nlet e = '\\''; \n"
+        );
+    }
+
+    #[test]
+    fn dedents_only_spaces_and_tabs() {
+        // other whitespace is typed like any other character
+        let source = lines("  \u{3000}a\n    b\n  \u{a0}c");
+        assert_eq!(dedent(&source), vec!["\u{3000}a\n", "  b\n", "\u{a0}c"]);
+    }
+
+    #[test]
+    fn chooses_blocks() {
+        let source = lines(
+            "use std::fmt;\n\
+             \n\
+             fn main() {\n    \
+                 if true {\n        \
+                     println!();\n    \
+                 }\n\
+             }",
+        );
+        let mut rng = StdRng::seed_from_u64(0);
+
+        // only the if statement is short enough
+        let range = choose_block(&source, 3, &mut rng).unwrap();
+        assert_eq!(
+            dedent(&source[range]),
+            vec!["if true {\n", "    println!();\n", "}"]
+        );
+
+        // the function also fits, and blank lines aren't counted
+        for _ in 0..20 {
+            let range = choose_block(&source, 5, &mut rng).unwrap();
+            assert!(range == (3..6) || range == (2..7), "{:?}", range);
+        }
+    }
+
+    #[test]
+    fn falls_back_to_lines() {
+        let source = lines("a = 1\nb = 2\nc = 3");
+        let range = choose_block(&source, 2, &mut StdRng::seed_from_u64(0)).unwrap();
+        assert!(range.len() <= 2);
+        assert!(choose_block(&lines("\n\n"), 2, &mut StdRng::seed_from_u64(0)).is_none());
+    }
+}
@@ -0,0 +1,131 @@
+use serde::Deserialize;
+use std::{
+    io::Read,
+    process::{Command, Stdio},
+    thread,
+    time::{Duration, Instant},
+};
+
+// How often a running command is checked on
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// A command whose output is typed, as named in the config.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Generator {
+    /// Command line, run by the system shell.
+    pub command: String,
+    /// Seconds the command may run for before it's stopped.
+    #[serde(default = "default_timeout")]
+    pub timeout: u64,
+}
+
+impl Generator {
+    pub fn new(command: String) -> Self {
+        Self {
+            command,
+            timeout: default_timeout(),
+        }
+    }
+
+    /// Run the command, returning what it wrote to standard output.
+    pub fn run(&self) -> Result<String, String> {
+        let mut child = shell(&self.command)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|err| format!("Couldn't run `{}`: {}", self.command, err))?;
+
+        // read output as it comes, so that the command never blocks on a full pipe
+        let mut stdout = child.stdout.take().unwrap();
+        let mut stderr = child.stderr.take().unwrap();
+        let stdout = thread::spawn(move || {
+            let mut output = Vec::new();
+            stdout.read_to_end(&mut output).map(|_| output)
+        });
+        let stderr = thread::spawn(move || {
+            let mut output = String::new();
+            let _ = stderr.read_to_string(&mut output);
+            output
+        });
+
+        let deadline = Instant::now() + Duration::from_secs(self.timeout);
+        let status = loop {
+            let status = child
+                .try_wait()
+                .map_err(|err| format!("Couldn't run `{}`: {}", self.command, err))?;
+            if let Some(status) = status {
+                break status;
+            }
+            if Instant::now() >= deadline {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(format!(
+                    "`{}` took longer than {}s, so it was stopped.",
+                    self.command, self.timeout
+                ));
+            }
+            thread::sleep(POLL_INTERVAL);
+        };
+
+        let stdout = stdout.join().unwrap();
+        let stderr = stderr.join().unwrap();
+        if !status.success() {
+            let mut message = format!("`{}` failed ({})", self.command, status);
+            if !stderr.trim().is_empty() {
+                message = format!("{}: {}", message, stderr.trim());
+            }
+            return Err(message);
+        }
+
+        let stdout = stdout.map_err(|err| format!("Couldn't read `{}`: {}", self.command, err))?;
+        String::from_utf8(stdout).map_err(|_| format!("`{}` wrote invalid UTF-8.", self.command))
+    }
+}
+
+fn default_timeout() -> u64 {
+    5
+}
+
+#[cfg(unix)]
+fn shell(command: &str) -> Command {
+    let mut shell = Command::new("sh");
+    shell.arg("-c").arg(command);
+    shell
+}
+
+#[cfg(windows)]
+fn shell(command: &str) -> Command {
+    let mut shell = Command::new("cmd");
+    shell.arg("/C").arg(command);
+    shell
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn captures_output() {
+        let output = Generator::new("echo hello; echo world".into()).run();
+        assert_eq!(output.unwrap(), "hello\nworld\n");
+    }
+
+    #[test]
+    fn reports_failures() {
+        let err = Generator::new("echo oops >&2; exit 3".into())
+            .run()
+            .unwrap_err();
+        assert!(
+            err.contains("exit status: 3") && err.ends_with("oops"),
+            "{}",
+            err
+        );
+
+        let generator = Generator {
+            command: "sleep 5".into(),
+            timeout: 0,
+        };
+        assert!(generator.run().unwrap_err().contains("took longer than 0s"));
+    }
+}
@@ -0,0 +1,184 @@
+use rand::{seq::SliceRandom, Rng};
+use std::{collections::HashMap, hash::Hash, str::FromStr};
+
+// Longest pseudo-word generated, in case the chain keeps going without ending a word
+const MAX_WORD_CHARS: usize = 20;
+
+/// What a Markov chain learns the order of.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    /// Letters within words, giving pronounceable pseudo-words.
+    Char,
+    /// Words within text, giving plausible sequences of real words.
+    Word,
+}
+
+impl FromStr for Level {
+    type Err = String;
+
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        match string {
+            "char" => Ok(Level::Char),
+            "word" => Ok(Level::Word),
+            _ => Err(format!(
+                "unknown Markov chain level `{}`, expected `char` or `word`",
+                string
+            )),
+        }
+    }
+}
+
+// A run of tokens, with `None` marking where a sequence starts or ends
+type State<T> = Vec<Option<T>>;
+
+/// Which tokens have followed each run of `order` tokens, and how heavily.
+struct Chain<T> {
+    order: usize,
+    transitions: HashMap<State<T>, Vec<(Option<T>, u64)>>,
+}
+
+impl<T: Clone + Eq + Hash> Chain<T> {
+    fn new(order: usize) -> Self {
+        Self {
+            order,
+            transitions: HashMap::new(),
+        }
+    }
+
+    /// Learn a sequence, counting each of its transitions `weight` times.
+    fn learn(&mut self, sequence: impl IntoIterator<Item = T>, weight: u32) {
+        let mut state = vec![None; self.order];
+        for token in sequence.into_iter().map(Some).chain([None]) {
+            self.transitions
+                .entry(state.clone())
+                .or_default()
+                .push((token.clone(), weight.into()));
+            state.remove(0);
+            state.push(token);
+        }
+    }
+
+    /// Walk the chain from the start of a sequence until it ends or reaches `limit` tokens.
+    fn walk(&self, limit: usize, rng: &mut impl Rng) -> Vec<T> {
+        let mut state = vec![None; self.order];
+        let mut tokens = Vec::new();
+        while tokens.len() < limit {
+            let next = self
+                .transitions
+                .get(&state)
+                .and_then(|next| next.choose_weighted(rng, |(_, weight)| *weight).ok())
+                .and_then(|(token, _)| token.clone());
+            let Some(token) = next else { break };
+            tokens.push(token.clone());
+            state.remove(0);
+            state.push(Some(token));
+        }
+        tokens
+    }
+}
+
+/// Generate `count` words from a Markov chain of the given order learned from some languages,
+/// each counting as much as its weight.
+pub fn generate(
+    languages: &[(Vec<&str>, u32)],
+    level: Level,
+    order: usize,
+    count: usize,
+    rng: &mut impl Rng,
+) -> Result<Vec<String>, String> {
+    if order == 0 {
+        return Err("the Markov chain order must be at least 1".into());
+    }
+    if languages.iter().all(|(words, _)| words.is_empty()) {
+        return Err("there are no words to learn a Markov chain from".into());
+    }
+
+    Ok(match level {
+        Level::Char => {
+            let mut chain = Chain::new(order);
+            for (words, weight) in languages {
+                for word in words {
+                    chain.learn(word.chars(), *weight);
+                }
+            }
+            (0..count)
+                .map(|_| chain.walk(MAX_WORD_CHARS, rng).into_iter().collect())
+                .collect()
+        }
+        Level::Word => {
+            let mut chain = Chain::new(order);
+            for (words, weight) in languages {
+                chain.learn(words.iter().copied(), *weight);
+            }
+
+            // each language is one long sequence, so start over whenever one ends
+            let mut words = Vec::with_capacity(count);
+            while words.len() < count {
+                words.extend(
+                    chain
+                        .walk(count - words.len(), rng)
+                        .into_iter()
+                        .map(ToOwned::to_owned),
+                );
+            }
+            words
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn generates_pseudo_words() {
+        let corpus = [(vec!["banana", "bandana", "cabana"], 1)];
+        let mut rng = StdRng::seed_from_u64(0);
+
+        let words = generate(&corpus, Level::Char, 2, 50, &mut rng).unwrap();
+        assert_eq!(words.len(), 50);
+        assert!(words.iter().all(|word| !word.is_empty()
+            && word.len() <= MAX_WORD_CHARS
+            && word.chars().all(|c| "abcdn".contains(c))));
+
+        // the same seed gives the same words
+        let again = generate(&corpus, Level::Char, 2, 50, &mut StdRng::seed_from_u64(0));
+        assert_eq!(again.unwrap(), words);
+    }
+
+    #[test]
+    fn generates_word_sequences() {
+        let text: Vec<&str> = "the cat sat on the mat".split_whitespace().collect();
+        let corpus = [(text.clone(), 1)];
+        let mut rng = StdRng::seed_from_u64(0);
+
+        let words = generate(&corpus, Level::Word, 1, 30, &mut rng).unwrap();
+        assert_eq!(words.len(), 30);
+        // each word follows one it follows in the corpus, or starts it over
+        for pair in words.windows(2) {
+            let follows = text.windows(2).any(|c| c[0] == pair[0] && c[1] == pair[1]);
+            assert!(
+                follows || (pair[0] == "mat" && pair[1] == "the"),
+                "{:?}",
+                pair
+            );
+        }
+
+        assert!(generate(&corpus, Level::Word, 0, 30, &mut rng).is_err());
+        assert!(generate(&[], Level::Char, 2, 30, &mut rng).is_err());
+    }
+
+    #[test]
+    fn weights_languages() {
+        let languages = [(vec!["aa"], u32::MAX), (vec!["bb"], 1)];
+        let words = generate(
+            &languages,
+            Level::Char,
+            1,
+            100,
+            &mut StdRng::seed_from_u64(0),
+        );
+        assert!(words.unwrap().iter().all(|word| !word.contains('b')));
+    }
+}
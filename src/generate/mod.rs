@@ -0,0 +1,371 @@
+pub mod code;
+pub mod command;
+pub mod markov;
+pub mod prose;
+pub mod symbols;
+
+use rand::{distributions::WeightedIndex, prelude::Distribution, seq::SliceRandom, Rng};
+use std::{fmt, num::NonZeroUsize, str::FromStr};
+
+// Fewer words than this make for a repetitive test
+const MIN_WORDS: usize = 10;
+
+/// Limits on which words of a language are used.
+#[derive(Debug, Default)]
+pub struct Filter {
+    /// Characters words may be made of, or any if none are given.
+    pub allowed: Option<String>,
+    /// Characters of which words must contain at least one, or none if none are given.
+    pub required: Option<String>,
+    pub min_length: Option<usize>,
+    pub max_length: Option<usize>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Error {
+    /// The minimum word length is greater than the maximum.
+    EmptyLengthRange,
+    /// Too few words passed the filter.
+    TooFewWords { matched: usize },
+    /// There were no words to sample from.
+    NoWords,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::EmptyLengthRange => write!(f, "the minimum word length is over the maximum"),
+            Error::TooFewWords { matched } => write!(
+                f,
+                "only {} words of the language match the word filters, but at least {} are needed",
+                matched, MIN_WORDS
+            ),
+            Error::NoWords => write!(f, "the language has no words"),
+        }
+    }
+}
+
+impl Filter {
+    fn is_empty(&self) -> bool {
+        self.allowed.is_none()
+            && self.required.is_none()
+            && self.min_length.is_none()
+            && self.max_length.is_none()
+    }
+
+    pub fn matches(&self, word: &str) -> bool {
+        let length = word.chars().count();
+
+        self.allowed
+            .as_ref()
+            .is_none_or(|allowed| word.chars().all(|c| allowed.contains(c)))
+            && self
+                .required
+                .as_ref()
+                .is_none_or(|required| word.chars().any(|c| required.contains(c)))
+            && self.min_length.is_none_or(|min| length >= min)
+            && self.max_length.is_none_or(|max| length <= max)
+    }
+
+    /// The words of a language which pass the filter.
+    pub fn apply<'a>(&self, words: Vec<&'a str>) -> Result<Vec<&'a str>, Error> {
+        if self.is_empty() {
+            return Ok(words);
+        }
+        if let (Some(min), Some(max)) = (self.min_length, self.max_length) {
+            if min > max {
+                return Err(Error::EmptyLengthRange);
+            }
+        }
+
+        let words: Vec<&str> = words.into_iter().filter(|w| self.matches(w)).collect();
+        if words.len() < MIN_WORDS {
+            return Err(Error::TooFewWords {
+                matched: words.len(),
+            });
+        }
+        Ok(words)
+    }
+}
+
+/// A language to draw words from, and how often relative to the others in a test.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WeightedLanguage {
+    pub name: String,
+    pub weight: u32,
+}
+
+impl FromStr for WeightedLanguage {
+    type Err = String;
+
+    /// Parse a language name, optionally followed by a weight like `english1000:3`.
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        let (name, weight) = match string.rsplit_once(':') {
+            Some((name, weight)) => match weight.parse() {
+                Ok(0) => return Err(format!("the weight of `{}` must be at least 1", name)),
+                Ok(weight) => (name, weight),
+                Err(_) => return Err(format!("invalid weight `{}` for `{}`", weight, name)),
+            },
+            None => (string, 1),
+        };
+        Ok(Self {
+            name: name.to_owned(),
+            weight,
+        })
+    }
+}
+
+/// How words are drawn from a language.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Sampling {
+    /// Every word is equally likely, and each is used once before any is used again.
+    #[default]
+    Uniform,
+    /// Words are weighted by their rank in the language file, like real text.
+    Zipf,
+}
+
+impl FromStr for Sampling {
+    type Err = String;
+
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        match string {
+            "uniform" => Ok(Sampling::Uniform),
+            "zipf" => Ok(Sampling::Zipf),
+            _ => Err(format!(
+                "unknown sampling mode `{}`, expected `uniform` or `zipf`",
+                string
+            )),
+        }
+    }
+}
+
+/// Settings for drawing words from a language.
+#[derive(Debug, Default)]
+pub struct Sampler {
+    pub mode: Sampling,
+    /// Only draw from this many words at the start of the language.
+    pub top: Option<NonZeroUsize>,
+    /// Never put the same word twice in a row.
+    pub no_repeats: bool,
+}
+
+impl Sampler {
+    /// Randomly sample `count` words from a language, whose words are in order of frequency
+    pub fn sample(
+        &self,
+        mut language: Vec<&str>,
+        count: usize,
+        rng: &mut impl Rng,
+    ) -> Result<Vec<String>, Error> {
+        if language.is_empty() {
+            return Err(Error::NoWords);
+        }
+        if let Some(top) = self.top {
+            language.truncate(top.get());
+        }
+
+        let mut contents = match self.mode {
+            Sampling::Uniform => sample_words(language, count, rng),
+            Sampling::Zipf => {
+                let weights = (1..=language.len()).map(|rank| 1.0 / rank as f64);
+                let index = WeightedIndex::new(weights).map_err(|_| Error::NoWords)?;
+                (0..count)
+                    .map(|_| language[index.sample(rng)].to_owned())
+                    .collect()
+            }
+        };
+
+        if self.no_repeats {
+            break_repeats(&mut contents);
+        }
+        Ok(contents)
+    }
+}
+
+/// Sample `count` words from several languages, choosing each word's language by its weight.
+pub fn mix(
+    mut languages: Vec<(Vec<&str>, u32)>,
+    sampler: &Sampler,
+    count: usize,
+    rng: &mut impl Rng,
+) -> Result<Vec<String>, Error> {
+    if languages.len() == 1 {
+        let (language, _) = languages.pop().unwrap();
+        return sampler.sample(language, count, rng);
+    }
+
+    let index = WeightedIndex::new(languages.iter().map(|(_, weight)| u64::from(*weight)))
+        .expect("Languages have no weight.");
+    let mut counts = vec![0; languages.len()];
+    for _ in 0..count {
+        counts[index.sample(rng)] += 1;
+    }
+
+    let mut contents = Vec::with_capacity(count);
+    for ((language, _), count) in languages.into_iter().zip(counts) {
+        contents.extend(sampler.sample(language, count, rng)?);
+    }
+    contents.shuffle(rng);
+
+    if sampler.no_repeats {
+        break_repeats(&mut contents);
+    }
+    Ok(contents)
+}
+
+/// Swap words around so that no word directly follows itself, where possible.
+fn break_repeats(words: &mut [String]) {
+    for i in 1..words.len() {
+        if words[i] != words[i - 1] {
+            continue;
+        }
+
+        // later words can be swapped in freely, since they're checked again when reached
+        let later = (i + 1..words.len()).find(|&j| words[j] != words[i - 1]);
+        // earlier words must fit between their new neighbours
+        let earlier = || {
+            (0..i - 1).find(|&j| {
+                words[j] != words[i - 1]
+                    && words.get(i + 1).is_none_or(|next| *next != words[j])
+                    && (j == 0 || words[j - 1] != words[i])
+                    && words[j + 1] != words[i]
+            })
+        };
+        if let Some(j) = later.or_else(earlier) {
+            words.swap(i, j);
+        }
+    }
+}
+
+/// Randomly sample `count` words from a language
+pub fn sample_words(mut language: Vec<&str>, count: usize, rng: &mut impl Rng) -> Vec<String> {
+    language.shuffle(rng);
+
+    let mut contents: Vec<_> = language
+        .into_iter()
+        .cycle()
+        .take(count)
+        .map(ToOwned::to_owned)
+        .collect();
+    contents.shuffle(rng);
+
+    contents
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    const WORDS: [&str; 12] = [
+        "a", "the", "quiz", "quartz", "jazz", "fizz", "buzz", "apple", "banana", "cherry", "date",
+        "fig",
+    ];
+
+    #[test]
+    fn filters_words() {
+        let filter = Filter {
+            allowed: Some("abcdefghijklmnopqrstuvwxyz".into()),
+            required: Some("z".into()),
+            ..Default::default()
+        };
+        assert!(filter.matches("quiz"));
+        assert!(!filter.matches("apple"));
+        assert!(!filter.matches("Quiz"));
+
+        let filter = Filter {
+            min_length: Some(4),
+            max_length: Some(5),
+            ..Default::default()
+        };
+        assert!(filter.matches("quiz"));
+        assert!(filter.matches("apple"));
+        assert!(!filter.matches("the"));
+        assert!(!filter.matches("banana"));
+    }
+
+    #[test]
+    fn samples_words() {
+        let mut rng = StdRng::seed_from_u64(0);
+
+        let zipf = Sampler {
+            mode: Sampling::Zipf,
+            ..Default::default()
+        };
+        let words = zipf.sample(WORDS.to_vec(), 1000, &mut rng).unwrap();
+        let count = |word| words.iter().filter(|w| *w == word).count();
+        assert!(count("a") > count("the"));
+        assert!(count("the") > count("fig"));
+
+        let top = Sampler {
+            top: NonZeroUsize::new(2),
+            no_repeats: true,
+            ..Default::default()
+        };
+        let words = top.sample(WORDS.to_vec(), 100, &mut rng).unwrap();
+        assert!(words.iter().all(|word| word == "a" || word == "the"));
+        assert!(words.windows(2).all(|pair| pair[0] != pair[1]));
+
+        let no_repeats = Sampler {
+            mode: Sampling::Zipf,
+            no_repeats: true,
+            ..Default::default()
+        };
+        let words = no_repeats.sample(WORDS.to_vec(), 1000, &mut rng).unwrap();
+        assert!(words.windows(2).all(|pair| pair[0] != pair[1]));
+
+        assert_eq!(zipf.sample(Vec::new(), 10, &mut rng), Err(Error::NoWords));
+    }
+
+    #[test]
+    fn rejects_too_few_words() {
+        let filter = Filter {
+            required: Some("z".into()),
+            ..Default::default()
+        };
+        assert_eq!(
+            filter.apply(WORDS.to_vec()),
+            Err(Error::TooFewWords { matched: 5 })
+        );
+
+        let filter = Filter {
+            max_length: Some(6),
+            ..Default::default()
+        };
+        assert_eq!(filter.apply(WORDS.to_vec()).map(|w| w.len()), Ok(12));
+
+        let filter = Filter {
+            min_length: Some(6),
+            max_length: Some(5),
+            ..Default::default()
+        };
+        assert_eq!(filter.apply(WORDS.to_vec()), Err(Error::EmptyLengthRange));
+    }
+
+    #[test]
+    fn parses_weighted_languages() {
+        let language = |name: &str, weight| WeightedLanguage {
+            name: name.into(),
+            weight,
+        };
+        assert_eq!("rust".parse(), Ok(language("rust", 1)));
+        assert_eq!("english1000:3".parse(), Ok(language("english1000", 3)));
+        assert!("rust:0".parse::<WeightedLanguage>().is_err());
+        assert!("rust:many".parse::<WeightedLanguage>().is_err());
+    }
+
+    #[test]
+    fn mixes_languages() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let languages = vec![(vec!["fn", "let", "mut"], 1), (vec!["the", "and", "of"], 3)];
+
+        let words = mix(languages, &Sampler::default(), 1000, &mut rng).unwrap();
+        assert_eq!(words.len(), 1000);
+        let code = words
+            .iter()
+            .filter(|word| ["fn", "let", "mut"].contains(&word.as_str()))
+            .count();
+        assert!((150..350).contains(&code), "{}", code);
+    }
+}
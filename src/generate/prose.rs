@@ -0,0 +1,263 @@
+use serde::Deserialize;
+
+/// How text files are turned into words to type.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(default)]
+pub struct ProseOptions {
+    /// Replace typographic characters, like smart quotes and dashes, with ones found on keyboards.
+    pub normalize: bool,
+}
+
+impl Default for ProseOptions {
+    fn default() -> Self {
+        Self { normalize: true }
+    }
+}
+
+/// Split prose into words, marking the last word of each paragraph with a trailing newline.
+///
+/// Lines are joined within paragraphs, which are separated by blank lines. Markdown is reduced to
+/// the text it displays.
+pub fn tokenize(text: &str, markdown: bool, options: ProseOptions) -> Vec<String> {
+    let mut paragraphs: Vec<Vec<String>> = vec![Vec::new()];
+    let mut in_code_block = false;
+
+    for line in text.lines() {
+        let mut line = line.trim();
+
+        if markdown {
+            if line.starts_with("```") || line.starts_with("~~~") {
+                in_code_block = !in_code_block;
+                continue;
+            }
+            if in_code_block {
+                continue;
+            }
+            if is_rule(line) {
+                line = "";
+            }
+        }
+
+        if line.is_empty() {
+            paragraphs.push(Vec::new());
+            continue;
+        }
+
+        let (line, own_paragraph) = if markdown {
+            strip_block_markup(line)
+        } else {
+            (line, false)
+        };
+        if own_paragraph {
+            paragraphs.push(Vec::new());
+        }
+
+        let line = if markdown {
+            strip_inline_markup(line)
+        } else {
+            line.to_owned()
+        };
+        let line = if options.normalize {
+            normalize(&line)
+        } else {
+            line
+        };
+        let paragraph = paragraphs.last_mut().unwrap();
+        paragraph.extend(line.split_whitespace().map(ToOwned::to_owned));
+
+        if own_paragraph {
+            paragraphs.push(Vec::new());
+        }
+    }
+
+    let mut words = Vec::new();
+    for mut paragraph in paragraphs.into_iter().filter(|p| !p.is_empty()) {
+        paragraph.last_mut().unwrap().push('\n');
+        words.extend(paragraph);
+    }
+    // the end of the text doesn't need marking
+    if let Some(last) = words.last_mut() {
+        last.pop();
+    }
+    words
+}
+
+/// Replace characters which aren't on most keyboards with ones which are.
+fn normalize(text: &str) -> String {
+    let mut normalized = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '‘' | '’' | '‚' | '‛' | '′' => normalized.push('\''),
+            '“' | '”' | '„' | '‟' | '″' | '«' | '»' => normalized.push('"'),
+            '‐' | '‑' | '‒' | '–' | '—' | '―' | '−' => normalized.push('-'),
+            '…' => normalized.push_str("..."),
+            // soft hyphens and zero-width characters can't be seen, let alone typed
+            '\u{ad}' | '\u{200b}' | '\u{200c}' | '\u{200d}' | '\u{2060}' | '\u{feff}' => {}
+            c => normalized.push(c),
+        }
+    }
+    normalized
+}
+
+/// Whether a Markdown line is a horizontal rule.
+fn is_rule(line: &str) -> bool {
+    let line: String = line.chars().filter(|c| !c.is_whitespace()).collect();
+    line.len() >= 3
+        && ['-', '*', '_']
+            .iter()
+            .any(|&rule| line.chars().all(|c| c == rule))
+}
+
+/// Remove the markup starting a Markdown line, and say whether the line stands alone as a
+/// heading or list item.
+fn strip_block_markup(mut line: &str) -> (&str, bool) {
+    while let Some(rest) = line.strip_prefix('>') {
+        line = rest.trim_start();
+    }
+
+    let heading = line.trim_start_matches('#');
+    if heading.len() < line.len() && (heading.is_empty() || heading.starts_with(' ')) {
+        return (heading.trim().trim_end_matches('#').trim_end(), true);
+    }
+
+    for bullet in ["- ", "* ", "+ "] {
+        if let Some(item) = line.strip_prefix(bullet) {
+            return (item, true);
+        }
+    }
+    let digits = line.chars().take_while(char::is_ascii_digit).count();
+    if digits > 0 {
+        if let Some(item) = line[digits..]
+            .strip_prefix(". ")
+            .or_else(|| line[digits..].strip_prefix(") "))
+        {
+            return (item, true);
+        }
+    }
+
+    (line, false)
+}
+
+/// Reduce inline Markdown, like emphasis and links, to the text it displays.
+fn strip_inline_markup(line: &str) -> String {
+    let mut text = String::with_capacity(line.len());
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => text.extend(chars.next()),
+            '*' | '`' => {}
+            // underscores only mark emphasis at the edges of words
+            '_' if text.ends_with(|c: char| c.is_alphanumeric())
+                && chars.peek().is_some_and(|c| c.is_alphanumeric()) =>
+            {
+                text.push('_')
+            }
+            '_' => {}
+            // images and links keep their text and lose their target
+            '!' if chars.peek() == Some(&'[') => {}
+            '[' => {}
+            ']' if chars.peek() == Some(&'(') => {
+                for c in chars.by_ref() {
+                    if c == ')' {
+                        break;
+                    }
+                }
+            }
+            ']' => {}
+            // HTML tags and autolinks
+            '<' if is_tag(chars.clone()) => {
+                let tag: String = chars.by_ref().take_while(|&c| c != '>').collect();
+                if tag.contains("://") || tag.contains('@') {
+                    text.push_str(&tag);
+                }
+            }
+            c => text.push(c),
+        }
+    }
+    text
+}
+
+/// Whether the text after a `<` is an HTML tag or autolink, rather than a less-than sign.
+fn is_tag(mut rest: impl Iterator<Item = char>) -> bool {
+    rest.next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '/' || c == '!')
+        && rest.any(|c| c == '>')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_paragraphs() {
+        let text = "The first\tparagraph,\nwrapped.\n\n\n  The\u{a0}second. \n";
+        assert_eq!(
+            tokenize(text, false, ProseOptions::default()),
+            vec!["The", "first", "paragraph,", "wrapped.\n", "The", "second."]
+        );
+    }
+
+    #[test]
+    fn normalizes_typography() {
+        let text = "“It’s—well…” she said.";
+        assert_eq!(
+            tokenize(text, false, ProseOptions::default()),
+            vec!["\"It's-well...\"", "she", "said."]
+        );
+        assert_eq!(
+            tokenize(text, false, ProseOptions { normalize: false }),
+            vec!["“It’s—well…”", "she", "said."]
+        );
+    }
+
+    #[test]
+    fn strips_markdown() {
+        let text = "# A *Title*\n\
+                    Some **bold**, `code` and [a link](https://example.com).\n\
+                    \n\
+                    ```rust\n\
+                    fn skipped() {}\n\
+                    ```\n\
+                    - an item with snake_case\n\
+                    > quoted <b>html</b>\n\
+                    \n\
+                    ---\n\
+                    ![alt text](image.png) \\*literal\\*";
+        assert_eq!(
+            tokenize(text, true, ProseOptions::default()),
+            vec![
+                "A",
+                "Title\n",
+                "Some",
+                "bold,",
+                "code",
+                "and",
+                "a",
+                "link.\n",
+                "an",
+                "item",
+                "with",
+                "snake_case\n",
+                "quoted",
+                "html\n",
+                "alt",
+                "text",
+                "*literal*",
+            ]
+        );
+    }
+
+    #[test]
+    fn keeps_less_than_signs() {
+        assert_eq!(
+            strip_inline_markup("if a < b and b > c"),
+            "if a < b and b > c"
+        );
+        assert_eq!(
+            strip_inline_markup("1<2, <br/> <https://x.org>"),
+            "1<2,  https://x.org"
+        );
+        assert_eq!(strip_inline_markup("an <unclosed tag"), "an <unclosed tag");
+    }
+}
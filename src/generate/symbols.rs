@@ -0,0 +1,221 @@
+use rand::{distributions::WeightedIndex, prelude::Distribution, seq::SliceRandom, Rng};
+use serde::Deserialize;
+
+const OPERATORS: [&str; 30] = [
+    "->", "=>", "::", "!=", "==", "<=", ">=", "&&", "||", "+=", "-=", "*=", "/=", "<<", ">>", "..",
+    "=", "+", "-", "*", "/", "%", "&", "|", "^", "!", "?", ";", "#", "@",
+];
+const BRACKETS: [(char, char); 4] = [('(', ')'), ('[', ']'), ('{', '}'), ('<', '>')];
+// Words joined into each identifier
+const IDENTIFIER_PARTS: std::ops::RangeInclusive<usize> = 2..=3;
+// Digits in each number
+const NUMBER_DIGITS: std::ops::RangeInclusive<usize> = 1..=9;
+
+/// How often each kind of token appears in symbol drills, relative to the others.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(default)]
+pub struct Weights {
+    /// Identifiers like `word_count`.
+    pub snake_case: u32,
+    /// Identifiers like `wordCount`.
+    pub camel_case: u32,
+    /// Identifiers like `word-count`.
+    pub kebab_case: u32,
+    /// Operators like `->` and `!=`.
+    pub operators: u32,
+    /// Brackets, empty or around a word, like `{}` and `[word]`.
+    pub brackets: u32,
+    /// String literals like `"word"`.
+    pub strings: u32,
+    /// Numbers like `1_000_000`, `0x1f` and `3.14`.
+    pub numbers: u32,
+}
+
+impl Default for Weights {
+    fn default() -> Self {
+        Self {
+            snake_case: 2,
+            camel_case: 2,
+            kebab_case: 1,
+            operators: 3,
+            brackets: 2,
+            strings: 1,
+            numbers: 1,
+        }
+    }
+}
+
+/// Generate `count` programmer-style tokens, building identifiers and literals from words drawn
+/// from some languages by their weights.
+pub fn generate(
+    languages: &[(Vec<&str>, u32)],
+    weights: Weights,
+    count: usize,
+    rng: &mut impl Rng,
+) -> Result<Vec<String>, String> {
+    let languages: Vec<(Vec<String>, u32)> = languages
+        .iter()
+        .map(|(words, weight)| {
+            let words: Vec<String> = words
+                .iter()
+                .filter(|word| !word.is_empty() && word.chars().all(char::is_alphanumeric))
+                .map(|word| word.to_lowercase())
+                .collect();
+            (words, *weight)
+        })
+        .filter(|(words, _)| !words.is_empty())
+        .collect();
+    let language = WeightedIndex::new(languages.iter().map(|(_, weight)| u64::from(*weight)))
+        .map_err(|_| "the language has no words to build identifiers from".to_string())?;
+
+    let kinds = WeightedIndex::new([
+        weights.snake_case,
+        weights.camel_case,
+        weights.kebab_case,
+        weights.operators,
+        weights.brackets,
+        weights.strings,
+        weights.numbers,
+    ])
+    .map_err(|_| "every kind of symbol has a weight of 0".to_string())?;
+
+    let word = |rng: &mut _| {
+        let (words, _) = &languages[language.sample(rng)];
+        words.choose(rng).unwrap().clone()
+    };
+    Ok((0..count)
+        .map(|_| match kinds.sample(rng) {
+            0 => identifier(word, "_", false, rng),
+            1 => identifier(word, "", true, rng),
+            2 => identifier(word, "-", false, rng),
+            3 => OPERATORS.choose(rng).unwrap().to_string(),
+            4 => {
+                let (open, close) = BRACKETS.choose(rng).unwrap();
+                let inner = if rng.gen() { word(rng) } else { String::new() };
+                format!("{}{}{}", open, inner, close)
+            }
+            5 => {
+                let quote = if rng.gen() { '"' } else { '\'' };
+                format!("{}{}{}", quote, word(rng), quote)
+            }
+            _ => number(rng),
+        })
+        .collect())
+}
+
+/// Join a few words with a separator, capitalizing all but the first if asked.
+fn identifier<R: Rng>(
+    word: impl Fn(&mut R) -> String,
+    separator: &str,
+    capitalize: bool,
+    rng: &mut R,
+) -> String {
+    let parts: Vec<String> = (0..rng.gen_range(IDENTIFIER_PARTS))
+        .map(|i| {
+            let part = word(rng);
+            let mut chars = part.chars();
+            match chars.next() {
+                Some(first) if capitalize && i > 0 => first.to_uppercase().chain(chars).collect(),
+                _ => part,
+            }
+        })
+        .collect();
+    parts.join(separator)
+}
+
+/// A decimal number with digit separators, a hexadecimal number or a decimal fraction.
+fn number(rng: &mut impl Rng) -> String {
+    match rng.gen_range(0..4) {
+        0 => format!("0x{:x}", rng.gen_range(0..=0xffff)),
+        1 => format!("{}.{}", rng.gen_range(0..100), rng.gen_range(0..100)),
+        _ => {
+            let digits = rng.gen_range(NUMBER_DIGITS);
+            let number = rng.gen_range(10u64.pow(digits as u32 - 1)..10u64.pow(digits as u32));
+            let number = number.to_string();
+
+            // group digits in threes from the right
+            let mut separated = String::new();
+            for (i, digit) in number.chars().enumerate() {
+                if i > 0 && (number.len() - i) % 3 == 0 {
+                    separated.push('_');
+                }
+                separated.push(digit);
+            }
+            separated
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn builds_identifiers() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let weights = Weights {
+            snake_case: 0,
+            camel_case: 1,
+            kebab_case: 0,
+            operators: 0,
+            brackets: 0,
+            strings: 0,
+            numbers: 0,
+        };
+
+        let tokens = generate(
+            &[(vec!["Word", "count", "don't"], 1)],
+            weights,
+            50,
+            &mut rng,
+        )
+        .unwrap();
+        for token in tokens {
+            // words are lowercased, and ones which can't be in identifiers are left out
+            assert!(token.starts_with("word") || token.starts_with("count"));
+            assert!(token.contains(['W', 'C']) && token.chars().all(char::is_alphabetic));
+        }
+    }
+
+    #[test]
+    fn mixes_tokens() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let tokens = generate(&[(vec!["word"], 1)], Weights::default(), 200, &mut rng).unwrap();
+
+        assert!(tokens.iter().any(|token| token.contains('_')));
+        assert!(tokens
+            .iter()
+            .any(|token| OPERATORS.contains(&token.as_str())));
+        assert!(tokens.iter().any(|token| token.starts_with(['"', '\''])));
+        assert!(tokens
+            .iter()
+            .any(|token| token.starts_with(|c: char| c.is_ascii_digit())));
+    }
+
+    #[test]
+    fn separates_digits() {
+        let mut rng = StdRng::seed_from_u64(0);
+        for _ in 0..100 {
+            let number = number(&mut rng);
+            if !number.contains(['x', '.']) {
+                assert!(number.split('_').skip(1).all(|group| group.len() == 3));
+                assert!(number
+                    .split('_')
+                    .all(|group| (1..=3).contains(&group.len())));
+            }
+        }
+
+        let weights = Weights {
+            snake_case: 0,
+            camel_case: 0,
+            kebab_case: 0,
+            operators: 0,
+            brackets: 0,
+            strings: 0,
+            numbers: 0,
+        };
+        assert!(generate(&[(vec!["word"], 1)], weights, 10, &mut rng).is_err());
+        assert!(generate(&[(vec!["don't"], 1)], Weights::default(), 10, &mut rng).is_err());
+    }
+}
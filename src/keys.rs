@@ -0,0 +1,414 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use serde::{de, Deserialize};
+use std::fmt;
+
+/// Something the app does in response to a key, rather than typing it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Quit,
+    Restart,
+    RetrySame,
+    Abort,
+    DeleteWord,
+    DeleteLine,
+    Pause,
+    ToggleHeatmap,
+    ToggleFingers,
+}
+
+/// A key together with the modifiers which must be held to press it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyChord {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl KeyChord {
+    pub const fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        Self { code, modifiers }
+    }
+
+    pub fn matches(&self, key: &KeyEvent) -> bool {
+        if key.kind == KeyEventKind::Release {
+            return false;
+        }
+
+        match (self.code, key.code) {
+            // the case of the character already says whether shift was held
+            (KeyCode::Char(a), KeyCode::Char(b)) => {
+                a == b
+                    && self.modifiers - KeyModifiers::SHIFT == key.modifiers - KeyModifiers::SHIFT
+            }
+            (a, b) => a == b && self.modifiers == key.modifiers,
+        }
+    }
+
+    /// The character this chord would type during a test, if any.
+    pub fn typed_char(&self) -> Option<char> {
+        if self
+            .modifiers
+            .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT)
+        {
+            return None;
+        }
+        match self.code {
+            KeyCode::Char(c) => Some(c),
+            KeyCode::Enter => Some(' '),
+            _ => None,
+        }
+    }
+
+    /// Whether this chord is needed for typing regardless of the prompt.
+    fn is_editing(&self) -> bool {
+        self.modifiers.is_empty() && self.code == KeyCode::Backspace
+            || self.typed_char() == Some(' ')
+    }
+}
+
+impl std::str::FromStr for KeyChord {
+    type Err = String;
+
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        let mut modifiers = KeyModifiers::NONE;
+        let mut rest = string;
+        // a trailing '-' is the key itself, not a separator
+        while let Some((modifier, key)) = rest.split_once('-').filter(|(_, key)| !key.is_empty()) {
+            modifiers |= match modifier.to_lowercase().as_str() {
+                "ctrl" | "c" => KeyModifiers::CONTROL,
+                "alt" | "a" | "m" => KeyModifiers::ALT,
+                "shift" | "s" => KeyModifiers::SHIFT,
+                _ => return Err(format!("unknown modifier `{}` in `{}`", modifier, string)),
+            };
+            rest = key;
+        }
+
+        let mut chars = rest.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) => KeyCode::Char(c),
+            _ => match rest.to_lowercase().as_str() {
+                "esc" | "escape" => KeyCode::Esc,
+                "enter" | "return" => KeyCode::Enter,
+                "tab" => KeyCode::Tab,
+                "backtab" => KeyCode::BackTab,
+                "backspace" => KeyCode::Backspace,
+                "delete" | "del" => KeyCode::Delete,
+                "insert" => KeyCode::Insert,
+                "space" => KeyCode::Char(' '),
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                "pageup" => KeyCode::PageUp,
+                "pagedown" => KeyCode::PageDown,
+                f if f.starts_with('f') => f[1..]
+                    .parse()
+                    .map(KeyCode::F)
+                    .map_err(|_| format!("unknown key `{}` in `{}`", rest, string))?,
+                _ => return Err(format!("unknown key `{}` in `{}`", rest, string)),
+            },
+        };
+
+        Ok(Self::new(code, modifiers))
+    }
+}
+
+impl fmt::Display for KeyChord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            write!(f, "ctrl-")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            write!(f, "alt-")?;
+        }
+        if self.modifiers.contains(KeyModifiers::SHIFT) {
+            write!(f, "shift-")?;
+        }
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "space"),
+            KeyCode::Char(c) => write!(f, "{}", c),
+            KeyCode::F(n) => write!(f, "f{}", n),
+            code => write!(f, "{}", format!("{:?}", code).to_lowercase()),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct KeyBindings {
+    // anywhere
+    #[serde(deserialize_with = "deserialize_chords")]
+    pub quit: Vec<KeyChord>,
+
+    // during a test
+    #[serde(deserialize_with = "deserialize_chords")]
+    pub restart: Vec<KeyChord>,
+    #[serde(deserialize_with = "deserialize_chords")]
+    pub retry_same: Vec<KeyChord>,
+    #[serde(deserialize_with = "deserialize_chords")]
+    pub abort: Vec<KeyChord>,
+    #[serde(deserialize_with = "deserialize_chords")]
+    pub delete_word: Vec<KeyChord>,
+    #[serde(deserialize_with = "deserialize_chords")]
+    pub delete_line: Vec<KeyChord>,
+    #[serde(deserialize_with = "deserialize_chords")]
+    pub pause: Vec<KeyChord>,
+
+    // on the results screen
+    #[serde(deserialize_with = "deserialize_chords")]
+    pub results_restart: Vec<KeyChord>,
+    #[serde(deserialize_with = "deserialize_chords")]
+    pub results_quit: Vec<KeyChord>,
+    #[serde(deserialize_with = "deserialize_chords")]
+    pub results_heatmap: Vec<KeyChord>,
+    #[serde(deserialize_with = "deserialize_chords")]
+    pub results_fingers: Vec<KeyChord>,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        let ctrl = |c| KeyChord::new(KeyCode::Char(c), KeyModifiers::CONTROL);
+        let plain = |code| KeyChord::new(code, KeyModifiers::NONE);
+
+        Self {
+            quit: vec![ctrl('c')],
+
+            restart: vec![ctrl('n')],
+            retry_same: vec![ctrl('r')],
+            abort: vec![plain(KeyCode::Esc)],
+            delete_word: vec![
+                ctrl('h'),
+                ctrl('w'),
+                KeyChord::new(KeyCode::Backspace, KeyModifiers::CONTROL),
+                KeyChord::new(KeyCode::Backspace, KeyModifiers::ALT),
+            ],
+            delete_line: vec![ctrl('u')],
+            pause: vec![ctrl('p')],
+
+            results_restart: vec![plain(KeyCode::Char('r'))],
+            results_quit: vec![plain(KeyCode::Char('q')), plain(KeyCode::Esc)],
+            results_heatmap: vec![plain(KeyCode::Char('h'))],
+            results_fingers: vec![plain(KeyCode::Char('f'))],
+        }
+    }
+}
+
+impl KeyBindings {
+    fn test_bindings(&self) -> [(&'static str, &[KeyChord], Action); 7] {
+        [
+            ("quit", &self.quit, Action::Quit),
+            ("restart", &self.restart, Action::Restart),
+            ("retry_same", &self.retry_same, Action::RetrySame),
+            ("abort", &self.abort, Action::Abort),
+            ("delete_word", &self.delete_word, Action::DeleteWord),
+            ("delete_line", &self.delete_line, Action::DeleteLine),
+            ("pause", &self.pause, Action::Pause),
+        ]
+    }
+
+    fn results_bindings(&self) -> [(&'static str, &[KeyChord], Action); 5] {
+        [
+            ("quit", &self.quit, Action::Quit),
+            ("results_restart", &self.results_restart, Action::Restart),
+            ("results_quit", &self.results_quit, Action::Quit),
+            (
+                "results_heatmap",
+                &self.results_heatmap,
+                Action::ToggleHeatmap,
+            ),
+            (
+                "results_fingers",
+                &self.results_fingers,
+                Action::ToggleFingers,
+            ),
+        ]
+    }
+
+    /// The action bound to a key during a test.
+    ///
+    /// Chords which would type a character in the prompt are left for typing.
+    pub fn test_action(&self, key: &KeyEvent, in_prompt: impl Fn(char) -> bool) -> Option<Action> {
+        self.test_bindings()
+            .into_iter()
+            .find(|(_, chords, _)| {
+                chords
+                    .iter()
+                    .any(|chord| chord.matches(key) && !chord.typed_char().is_some_and(&in_prompt))
+            })
+            .map(|(_, _, action)| action)
+    }
+
+    /// The action bound to a key on the results screen.
+    pub fn results_action(&self, key: &KeyEvent) -> Option<Action> {
+        self.results_bindings()
+            .into_iter()
+            .find(|(_, chords, _)| chords.iter().any(|chord| chord.matches(key)))
+            .map(|(_, _, action)| action)
+    }
+
+    /// Check that no chord is bound to two different actions on the same screen, and that no
+    /// test chord is needed for typing.
+    pub fn validate(&self) -> Result<(), String> {
+        fn check_conflicts(bindings: &[(&'static str, &[KeyChord], Action)]) -> Result<(), String> {
+            for (i, (name, chords, action)) in bindings.iter().enumerate() {
+                for (other_name, other_chords, other_action) in &bindings[i + 1..] {
+                    if action == other_action {
+                        continue;
+                    }
+                    if let Some(chord) = chords.iter().find(|c| other_chords.contains(c)) {
+                        return Err(format!(
+                            "`{}` is bound to both `{}` and `{}`",
+                            chord, name, other_name
+                        ));
+                    }
+                }
+            }
+            Ok(())
+        }
+
+        check_conflicts(&self.test_bindings())?;
+        check_conflicts(&self.results_bindings())?;
+
+        for (name, chords, _) in self.test_bindings() {
+            if let Some(chord) = chords.iter().find(|c| c.is_editing()) {
+                return Err(format!(
+                    "`{}` is needed for typing and can't be bound to `{}`",
+                    chord, name
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Find a test chord which would also type one of the given characters.
+    pub fn prompt_conflict(
+        &self,
+        mut in_prompt: impl FnMut(char) -> bool,
+    ) -> Option<(&'static str, KeyChord)> {
+        self.test_bindings()
+            .into_iter()
+            .find_map(|(name, chords, _)| {
+                chords
+                    .iter()
+                    .find(|chord| chord.typed_char().is_some_and(&mut in_prompt))
+                    .map(|chord| (name, *chord))
+            })
+    }
+}
+
+fn deserialize_chords<'de, D>(deserializer: D) -> Result<Vec<KeyChord>, D::Error>
+where
+    D: de::Deserializer<'de>,
+{
+    struct ChordsVisitor;
+    impl<'de> de::Visitor<'de> for ChordsVisitor {
+        type Value = Vec<KeyChord>;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("a key chord or a list of key chords")
+        }
+
+        fn visit_str<E: de::Error>(self, value: &str) -> Result<Self::Value, E> {
+            Ok(vec![value.parse().map_err(E::custom)?])
+        }
+
+        fn visit_seq<A: de::SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+            let mut chords = Vec::new();
+            while let Some(chord) = seq.next_element::<&str>()? {
+                chords.push(chord.parse().map_err(de::Error::custom)?);
+            }
+            Ok(chords)
+        }
+    }
+
+    deserializer.deserialize_any(ChordsVisitor)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chord(string: &str) -> KeyChord {
+        string.parse().expect("failed to parse chord")
+    }
+
+    #[test]
+    fn parses_chords() {
+        assert_eq!(
+            chord("ctrl-c"),
+            KeyChord::new(KeyCode::Char('c'), KeyModifiers::CONTROL)
+        );
+        assert_eq!(
+            chord("C-A-x"),
+            KeyChord::new(
+                KeyCode::Char('x'),
+                KeyModifiers::CONTROL | KeyModifiers::ALT
+            )
+        );
+        assert_eq!(
+            chord("esc"),
+            KeyChord::new(KeyCode::Esc, KeyModifiers::NONE)
+        );
+        assert_eq!(
+            chord("f5"),
+            KeyChord::new(KeyCode::F(5), KeyModifiers::NONE)
+        );
+        assert_eq!(
+            chord("alt--"),
+            KeyChord::new(KeyCode::Char('-'), KeyModifiers::ALT)
+        );
+        assert!("hyper-x".parse::<KeyChord>().is_err());
+        assert!("ctrl-nope".parse::<KeyChord>().is_err());
+
+        assert_eq!(
+            chord("ctrl-alt-backspace").to_string(),
+            "ctrl-alt-backspace"
+        );
+    }
+
+    #[test]
+    fn matches_keys() {
+        let key = |code, modifiers| KeyEvent::new(code, modifiers);
+
+        assert!(chord("Q").matches(&key(KeyCode::Char('Q'), KeyModifiers::SHIFT)));
+        assert!(chord("ctrl-c").matches(&key(KeyCode::Char('c'), KeyModifiers::CONTROL)));
+        assert!(!chord("ctrl-c").matches(&key(KeyCode::Char('c'), KeyModifiers::NONE)));
+        assert!(!chord("esc").matches(&key(KeyCode::Esc, KeyModifiers::ALT)));
+    }
+
+    #[test]
+    fn validates_bindings() {
+        assert!(KeyBindings::default().validate().is_ok());
+
+        let conflicting = KeyBindings {
+            pause: vec![chord("ctrl-n")],
+            ..Default::default()
+        };
+        assert!(conflicting.validate().is_err());
+
+        let editing = KeyBindings {
+            abort: vec![chord("backspace")],
+            ..Default::default()
+        };
+        assert!(editing.validate().is_err());
+
+        let typed = KeyBindings {
+            pause: vec![chord("`")],
+            ..Default::default()
+        };
+        assert!(typed.prompt_conflict(|c| "abc".contains(c)).is_none());
+        assert_eq!(
+            typed.prompt_conflict(|c| "a`c".contains(c)),
+            Some(("pause", chord("`")))
+        );
+        assert_eq!(
+            typed.test_action(
+                &KeyEvent::new(KeyCode::Char('`'), KeyModifiers::NONE),
+                |c| { "a`c".contains(c) }
+            ),
+            None
+        );
+    }
+}
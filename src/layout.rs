@@ -0,0 +1,393 @@
+use crate::keys::KeyChord;
+use crate::Resources;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::Deserialize;
+use std::{fmt, str};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Finger {
+    LeftPinky,
+    LeftRing,
+    LeftMiddle,
+    LeftIndex,
+    Thumb,
+    RightIndex,
+    RightMiddle,
+    RightRing,
+    RightPinky,
+}
+
+impl fmt::Display for Finger {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Finger::LeftPinky => "left pinky",
+            Finger::LeftRing => "left ring finger",
+            Finger::LeftMiddle => "left middle finger",
+            Finger::LeftIndex => "left index finger",
+            Finger::Thumb => "thumb",
+            Finger::RightIndex => "right index finger",
+            Finger::RightMiddle => "right middle finger",
+            Finger::RightRing => "right ring finger",
+            Finger::RightPinky => "right pinky",
+        })
+    }
+}
+
+impl Finger {
+    /// The finger written as a digit in a layout's `fingers` rows, counting from 1 for the left
+    /// pinky to 9 for the right pinky, with 5 for the thumbs.
+    fn from_digit(c: char) -> Option<Self> {
+        Some(match c {
+            '1' => Finger::LeftPinky,
+            '2' => Finger::LeftRing,
+            '3' => Finger::LeftMiddle,
+            '4' => Finger::LeftIndex,
+            '5' => Finger::Thumb,
+            '6' => Finger::RightIndex,
+            '7' => Finger::RightMiddle,
+            '8' => Finger::RightRing,
+            '9' => Finger::RightPinky,
+            _ => return None,
+        })
+    }
+
+    /// The hand the finger is on, or none for the thumbs, which can press the space bar with
+    /// either hand.
+    pub fn hand(self) -> Option<Hand> {
+        match self {
+            Finger::LeftPinky | Finger::LeftRing | Finger::LeftMiddle | Finger::LeftIndex => {
+                Some(Hand::Left)
+            }
+            Finger::Thumb => None,
+            Finger::RightIndex | Finger::RightMiddle | Finger::RightRing | Finger::RightPinky => {
+                Some(Hand::Right)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Hand {
+    Left,
+    Right,
+}
+
+impl fmt::Display for Hand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Hand::Left => "left hand",
+            Hand::Right => "right hand",
+        })
+    }
+}
+
+// Fingers for each column of the letter rows in standard touch typing, for layouts which don't
+// set their own. The number row is offset one column to the left of these.
+const COLUMN_FINGERS: [Finger; 13] = [
+    Finger::LeftPinky,
+    Finger::LeftRing,
+    Finger::LeftMiddle,
+    Finger::LeftIndex,
+    Finger::LeftIndex,
+    Finger::RightIndex,
+    Finger::RightIndex,
+    Finger::RightMiddle,
+    Finger::RightRing,
+    Finger::RightPinky,
+    Finger::RightPinky,
+    Finger::RightPinky,
+    Finger::RightPinky,
+];
+
+/// Where a key is on the keyboard.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Position {
+    /// Row, counting down from the number row.
+    pub row: usize,
+    /// Column, counting from the left of the row.
+    pub col: usize,
+    pub shifted: bool,
+}
+
+/// A physical key, and whether shift was held to press it.
+///
+/// Character keys are identified by what they type without shift, so presses of a key are
+/// counted together however the terminal reports modifiers and event kinds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeyId {
+    pub code: KeyCode,
+    pub shifted: bool,
+}
+
+impl fmt::Display for KeyId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let modifiers = if self.shifted {
+            KeyModifiers::SHIFT
+        } else {
+            KeyModifiers::NONE
+        };
+        KeyChord::new(self.code, modifiers).fmt(f)
+    }
+}
+
+/// The characters typed by each key of a keyboard.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Layout {
+    /// Rows of unshifted characters from the number row down.
+    pub rows: Vec<String>,
+    /// Rows of shifted characters, or uppercase letters when left out.
+    #[serde(default)]
+    pub shifted: Vec<String>,
+    /// Rows of digits for the finger pressing each key, or standard touch typing when left out.
+    #[serde(default)]
+    pub fingers: Vec<String>,
+}
+
+impl Layout {
+    pub fn from_toml(source: &str) -> Result<Self, String> {
+        let mut layout: Self = toml::from_str(source).map_err(|err| err.to_string())?;
+
+        if layout.shifted.is_empty() {
+            layout.shifted = layout
+                .rows
+                .iter()
+                .map(|row| row.chars().flat_map(char::to_uppercase).collect())
+                .collect();
+        }
+
+        let row_lengths = |rows: &[String]| -> Vec<usize> {
+            rows.iter().map(|row| row.chars().count()).collect()
+        };
+        if row_lengths(&layout.rows) != row_lengths(&layout.shifted) {
+            return Err("`shifted` rows must be the same lengths as `rows`".into());
+        }
+        if !layout.fingers.is_empty() {
+            if row_lengths(&layout.rows) != row_lengths(&layout.fingers) {
+                return Err("`fingers` rows must be the same lengths as `rows`".into());
+            }
+            if let Some(c) = layout
+                .fingers
+                .iter()
+                .flat_map(|row| row.chars())
+                .find(|&c| Finger::from_digit(c).is_none())
+            {
+                return Err(format!("`{}` in `fingers` isn't a finger from 1 to 9", c));
+            }
+        }
+
+        Ok(layout)
+    }
+
+    /// Load a layout built into ttyper, if there's one with the name.
+    pub fn builtin(name: &str) -> Option<Result<Self, String>> {
+        let file = Resources::get(&format!("layout/{}.toml", name))?;
+        Some(
+            str::from_utf8(&file.data)
+                .map_err(|err| err.to_string())
+                .and_then(Self::from_toml),
+        )
+    }
+
+    /// Where the key typing a character is.
+    pub fn position(&self, c: char) -> Option<Position> {
+        [(&self.rows, false), (&self.shifted, true)]
+            .into_iter()
+            .find_map(|(rows, shifted)| {
+                rows.iter().enumerate().find_map(|(row, keys)| {
+                    keys.chars()
+                        .position(|key| key == c)
+                        .map(|col| Position { row, col, shifted })
+                })
+            })
+    }
+
+    /// The character typed by the key at a position.
+    pub fn char_at(&self, position: Position) -> Option<char> {
+        let rows = if position.shifted {
+            &self.shifted
+        } else {
+            &self.rows
+        };
+        rows.get(position.row)?.chars().nth(position.col)
+    }
+
+    /// The finger which presses the key at a position, as set by the layout or else in standard
+    /// touch typing.
+    pub fn finger(&self, position: Position) -> Finger {
+        let set = self
+            .fingers
+            .get(position.row)
+            .and_then(|row| row.chars().nth(position.col))
+            .and_then(Finger::from_digit);
+        if let Some(finger) = set {
+            return finger;
+        }
+
+        let col = match position.row {
+            0 => position.col.saturating_sub(1),
+            _ => position.col,
+        };
+        COLUMN_FINGERS[col.min(COLUMN_FINGERS.len() - 1)]
+    }
+
+    /// Where a character is typed and with which finger, including the space bar.
+    pub fn locate(&self, c: char) -> Option<(Option<Position>, Finger)> {
+        match c {
+            ' ' => Some((None, Finger::Thumb)),
+            c => self.position(c).map(|pos| (Some(pos), self.finger(pos))),
+        }
+    }
+
+    /// The physical key pressed to type a key, falling back to letter case for characters
+    /// which aren't on the layout.
+    pub fn key_id(&self, key: &KeyEvent) -> KeyId {
+        let c = match key.code {
+            KeyCode::Char(c) => c,
+            code => {
+                return KeyId {
+                    code,
+                    shifted: key.modifiers.contains(KeyModifiers::SHIFT),
+                }
+            }
+        };
+
+        if let Some(position) = self.position(c) {
+            let unshifted = self.char_at(Position {
+                shifted: false,
+                ..position
+            });
+            return KeyId {
+                code: KeyCode::Char(unshifted.unwrap_or(c)),
+                shifted: position.shifted,
+            };
+        }
+
+        let mut lower = c.to_lowercase();
+        match (lower.next(), lower.next()) {
+            (Some(lower), None) if lower != c => KeyId {
+                code: KeyCode::Char(lower),
+                shifted: true,
+            },
+            _ => KeyId {
+                code: KeyCode::Char(c),
+                shifted: false,
+            },
+        }
+    }
+
+    /// Translate a key typed with this layout into the key at the same position in another.
+    pub fn remap(&self, to: &Layout, mut key: KeyEvent) -> KeyEvent {
+        if key
+            .modifiers
+            .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT)
+        {
+            return key;
+        }
+
+        if let KeyCode::Char(c) = key.code {
+            if let Some(c) = self.position(c).and_then(|pos| to.char_at(pos)) {
+                key.code = KeyCode::Char(c);
+            }
+        }
+        key
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn loads_builtin_layouts() {
+        for name in ["qwerty", "dvorak", "colemak", "colemak-dh", "workman"] {
+            let layout = Layout::builtin(name).unwrap().unwrap();
+            assert_eq!(layout.rows.len(), 4, "{}", name);
+            assert_eq!(
+                layout.position('a').map(|p| (p.row, p.col)),
+                Some((2, 0)),
+                "{}",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn remaps_keys() {
+        let qwerty = Layout::builtin("qwerty").unwrap().unwrap();
+        let dvorak = Layout::builtin("dvorak").unwrap().unwrap();
+        let key = |c| KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE);
+
+        assert_eq!(qwerty.remap(&dvorak, key('s')).code, KeyCode::Char('o'));
+        assert_eq!(qwerty.remap(&dvorak, key('S')).code, KeyCode::Char('O'));
+        assert_eq!(qwerty.remap(&dvorak, key('q')).code, KeyCode::Char('\''));
+        assert_eq!(qwerty.remap(&dvorak, key('é')).code, KeyCode::Char('é'));
+
+        let ctrl_s = KeyEvent::new(KeyCode::Char('s'), KeyModifiers::CONTROL);
+        assert_eq!(qwerty.remap(&dvorak, ctrl_s), ctrl_s);
+    }
+
+    #[test]
+    fn assigns_fingers() {
+        let qwerty = Layout::builtin("qwerty").unwrap().unwrap();
+        let finger = |c| qwerty.locate(c).unwrap().1;
+
+        assert_eq!(finger('a'), Finger::LeftPinky);
+        assert_eq!(finger('1'), Finger::LeftPinky);
+        assert_eq!(finger('G'), Finger::LeftIndex);
+        assert_eq!(finger('6'), Finger::RightIndex);
+        assert_eq!(finger('m'), Finger::RightIndex);
+        assert_eq!(finger('\''), Finger::RightPinky);
+        assert_eq!(finger(' '), Finger::Thumb);
+    }
+
+    #[test]
+    fn identifies_physical_keys() {
+        let qwerty = Layout::builtin("qwerty").unwrap().unwrap();
+        let id = |c, modifiers| qwerty.key_id(&KeyEvent::new(KeyCode::Char(c), modifiers));
+
+        assert_eq!(id('A', KeyModifiers::SHIFT), id('A', KeyModifiers::NONE));
+        assert_eq!(id('A', KeyModifiers::SHIFT).code, KeyCode::Char('a'));
+        assert!(id('A', KeyModifiers::SHIFT).shifted);
+        assert!(!id('a', KeyModifiers::NONE).shifted);
+        assert_eq!(id('!', KeyModifiers::SHIFT).to_string(), "shift-1");
+        assert_eq!(id('É', KeyModifiers::SHIFT).to_string(), "shift-é");
+        assert_eq!(id(' ', KeyModifiers::NONE).to_string(), "space");
+    }
+
+    #[test]
+    fn defaults_shifted_rows() {
+        let layout = Layout::from_toml(r#"rows = ["ab"]"#).unwrap();
+        assert_eq!(layout.shifted, vec!["AB".to_string()]);
+        assert!(Layout::from_toml(
+            r#"rows = ["ab"]
+            shifted = ["A"]"#
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn reads_fingers() {
+        let layout = Layout::from_toml(
+            r#"rows = ["12", "ab"]
+            fingers = ["14", "26"]"#,
+        )
+        .unwrap();
+        let finger = |c| layout.locate(c).unwrap().1;
+        assert_eq!(finger('2'), Finger::LeftIndex);
+        assert_eq!(finger('a'), Finger::LeftRing);
+        assert_eq!(finger('B'), Finger::RightIndex);
+
+        assert!(Layout::from_toml(
+            r#"rows = ["ab"]
+            fingers = ["1"]"#
+        )
+        .is_err());
+        assert!(Layout::from_toml(
+            r#"rows = ["ab"]
+            fingers = ["10"]"#
+        )
+        .is_err());
+    }
+}
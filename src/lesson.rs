@@ -0,0 +1,212 @@
+use crate::layout::{Layout, Position};
+use crate::state::StateFile;
+use crate::test::results::{LessonInfo, Results, WPM_PER_CPS};
+
+use rand::{distributions::WeightedIndex, prelude::Distribution, seq::SliceRandom, Rng};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+// Rows in the order they're learned: home, top, bottom, then numbers
+const ROW_ORDER: [usize; 4] = [2, 1, 3, 0];
+// Columns in the order they're learned within a row, from the index fingers outwards
+const COLUMN_ORDER: [usize; 10] = [3, 6, 2, 7, 1, 8, 0, 9, 4, 5];
+
+/// Accuracy needed in a lesson to unlock the next key.
+pub const UNLOCK_ACCURACY: f64 = 0.95;
+/// Adjusted WPM needed in a lesson to unlock the next key.
+pub const UNLOCK_WPM: f64 = 20.0;
+
+// Fewer real words than this are topped up with pseudo-words
+const MIN_REAL_WORDS: usize = 20;
+// Pseudo-words added when there are too few real words
+const PSEUDO_WORDS: usize = 50;
+// Lengths of pseudo-words
+const PSEUDO_WORD_LENGTHS: std::ops::RangeInclusive<usize> = 2..=5;
+
+/// A touch typing course which starts with the home row and unlocks one letter at a time.
+#[derive(Debug)]
+pub struct Lesson {
+    /// Letters in the order they're unlocked.
+    keys: Vec<char>,
+    unlocked: usize,
+}
+
+impl Lesson {
+    /// Start a course on a layout, with some number of letters already unlocked.
+    pub fn new(layout: &Layout, unlocked: Option<usize>) -> Self {
+        let letter_at = |row, col| {
+            layout
+                .char_at(Position {
+                    row,
+                    col,
+                    shifted: false,
+                })
+                .filter(|c| c.is_alphabetic())
+        };
+
+        let mut keys: Vec<char> = Vec::new();
+        for row in ROW_ORDER {
+            let row_len = layout.rows.get(row).map_or(0, |keys| keys.chars().count());
+            let columns = COLUMN_ORDER.into_iter().chain(COLUMN_ORDER.len()..row_len);
+            for c in columns.filter_map(|col| letter_at(row, col)) {
+                if !keys.contains(&c) {
+                    keys.push(c);
+                }
+            }
+        }
+
+        // the whole home row is unlocked from the start, counting letters it repeats once
+        let mut home_row: Vec<char> = (0..COLUMN_ORDER.len())
+            .filter_map(|col| letter_at(ROW_ORDER[0], col))
+            .collect();
+        home_row.sort_unstable();
+        home_row.dedup();
+        let unlocked = unlocked.unwrap_or(0).clamp(home_row.len(), keys.len());
+
+        Self { keys, unlocked }
+    }
+
+    pub fn unlocked(&self) -> &[char] {
+        &self.keys[..self.unlocked]
+    }
+
+    pub fn next_key(&self) -> Option<char> {
+        self.keys.get(self.unlocked).copied()
+    }
+
+    /// Randomly sample words which only use unlocked letters from languages, drawing each word's
+    /// language by its weight, along with made-up words if the languages have too few.
+    pub fn words(
+        &self,
+        languages: &[(&str, u32)],
+        count: usize,
+        rng: &mut impl Rng,
+    ) -> Result<Vec<String>, String> {
+        let unlocked = self.unlocked();
+        if unlocked.is_empty() {
+            return Err("The layout has no letters to learn.".into());
+        }
+        let mut pools: Vec<(Vec<String>, u32)> = languages
+            .iter()
+            .map(|(language, weight)| {
+                let words: Vec<String> = language
+                    .lines()
+                    .filter(|word| !word.is_empty() && word.chars().all(|c| unlocked.contains(&c)))
+                    .map(ToOwned::to_owned)
+                    .collect();
+                (words, *weight)
+            })
+            .filter(|(words, weight)| !words.is_empty() && *weight > 0)
+            .collect();
+
+        // made-up words outnumber so few real ones that weighing the languages makes no odds
+        if pools.iter().map(|(words, _)| words.len()).sum::<usize>() < MIN_REAL_WORDS {
+            let mut pool: Vec<String> = pools.into_iter().flat_map(|(words, _)| words).collect();
+            pool.extend((0..PSEUDO_WORDS).map(|_| {
+                (0..rng.gen_range(PSEUDO_WORD_LENGTHS))
+                    .map(|_| *unlocked.choose(rng).unwrap())
+                    .collect()
+            }));
+            pools = vec![(pool, 1)];
+        }
+
+        // every pool has words and weight
+        let index = WeightedIndex::new(pools.iter().map(|(_, weight)| u64::from(*weight))).unwrap();
+        Ok((0..count)
+            .map(|_| {
+                let (pool, _) = &pools[index.sample(rng)];
+                pool.choose(rng).unwrap().clone()
+            })
+            .collect())
+    }
+
+    /// Unlock the next key if a test's results meet the thresholds and it wasn't flagged as
+    /// suspicious.
+    pub fn record(&mut self, results: &Results) -> LessonInfo {
+        let accuracy = f64::from(results.accuracy.overall);
+        let wpm = results.timing.overall_cps * WPM_PER_CPS * accuracy;
+
+        let unlocked = if results.complete && results.flags.is_empty() && passed(accuracy, wpm) {
+            let key = self.next_key();
+            self.unlocked = (self.unlocked + 1).min(self.keys.len());
+            key
+        } else {
+            None
+        };
+
+        LessonInfo {
+            keys: self.unlocked().to_vec(),
+            unlocked,
+            next: self.next_key(),
+            save_error: None,
+        }
+    }
+
+    pub fn unlocked_count(&self) -> usize {
+        self.unlocked
+    }
+}
+
+/// Whether a lesson was typed well enough to move on.
+fn passed(accuracy: f64, wpm: f64) -> bool {
+    accuracy >= UNLOCK_ACCURACY && wpm >= UNLOCK_WPM
+}
+
+/// Letters unlocked on each layout, saved between runs.
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct Progress {
+    #[serde(default)]
+    pub unlocked: BTreeMap<String, usize>,
+}
+
+impl StateFile for Progress {
+    const NAME: &'static str = "lesson progress";
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn starts_on_home_row() {
+        let lesson = Lesson::new(&Layout::builtin("qwerty").unwrap().unwrap(), None);
+        assert_eq!(lesson.unlocked().iter().collect::<String>(), "fjdkslagh");
+        assert_eq!(lesson.next_key(), Some('r'));
+
+        let lesson = Lesson::new(&Layout::builtin("qwerty").unwrap().unwrap(), Some(1000));
+        assert_eq!(lesson.unlocked().len(), 26);
+        assert_eq!(lesson.next_key(), None);
+
+        let repeated = Layout::from_toml(r#"rows = ["1", "2", "aab"]"#).unwrap();
+        let lesson = Lesson::new(&repeated, None);
+        assert_eq!(lesson.unlocked().iter().collect::<String>(), "ba");
+    }
+
+    #[test]
+    fn only_uses_unlocked_letters() {
+        let lesson = Lesson::new(&Layout::builtin("qwerty").unwrap().unwrap(), None);
+        let mut rng = StdRng::seed_from_u64(0);
+
+        let words = lesson
+            .words(&[("the\nask\nflask\nglad\nquiz", 1)], 1000, &mut rng)
+            .unwrap();
+        assert_eq!(words.len(), 1000);
+        assert!(words
+            .iter()
+            .all(|word| word.chars().all(|c| "asdfghjkl".contains(c))));
+        assert!(words.iter().any(|word| word == "flask"));
+        assert!(!words.iter().any(|word| word == "the"));
+
+        let numbers = Layout::from_toml(r#"rows = ["1234", "5678", "90"]"#).unwrap();
+        let lesson = Lesson::new(&numbers, None);
+        assert!(lesson.words(&[("the", 1)], 10, &mut rng).is_err());
+    }
+
+    #[test]
+    fn requires_accuracy_and_speed() {
+        assert!(passed(0.96, 25.0));
+        assert!(!passed(0.9, 25.0));
+        assert!(!passed(0.99, 15.0));
+    }
+}
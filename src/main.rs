@@ -1,28 +1,54 @@
+mod book;
+mod challenge;
 mod config;
+mod generate;
+mod keys;
+mod layout;
+mod lesson;
+mod state;
 mod test;
 mod ui;
 
+use book::{Book, Bookmarks, Chunk};
+use challenge::Challenge;
 use config::Config;
-use test::{results::Results, Test};
+use keys::Action;
+use generate::{
+    code, command::Generator, markov, prose, sample_words, symbols, Filter, Sampler, Sampling,
+    WeightedLanguage,
+};
+use layout::Layout;
+use lesson::{Lesson, Progress};
+use state::StateFile;
+use test::{
+    results::{ChallengeInfo, Results},
+    Test,
+};
 
 use crossterm::{
     self, cursor,
-    event::{self, Event, KeyCode, KeyEvent, KeyModifiers},
+    event::{self, Event, KeyEventKind},
     execute, terminal,
 };
-use rand::{seq::SliceRandom, thread_rng};
+use rand::{rngs::StdRng, Rng, SeedableRng};
 use rust_embed::RustEmbed;
 use std::{
+    cell::RefCell,
     ffi::OsString,
     fs,
-    io::{self, BufRead},
+    io,
     num,
-    path::PathBuf,
-    str,
+    path::{Path, PathBuf},
+    process, str,
+    time::Duration,
 };
 use structopt::StructOpt;
+use ui::{Heatmap, ResultsScreen, TestScreen};
 use ratatui::{backend::CrosstermBackend, terminal::Terminal};
 
+// Maximum time between redraws of a time-limited test
+const TICK_RATE: Duration = Duration::from_secs(1);
+
 #[derive(RustEmbed)]
 #[folder = "resources/runtime"]
 struct Resources;
@@ -48,73 +74,335 @@ struct Opt {
     #[structopt(long, parse(from_os_str))]
     language_file: Option<PathBuf>,
 
-    /// Specify test language
-    #[structopt(short, long)]
-    language: Option<String>,
+    /// Specify test language, repeated with optional weights to mix languages
+    #[structopt(short, long, number_of_values = 1, value_name = "language[:weight]")]
+    language: Vec<WeightedLanguage>,
 
     /// List installed languages
     #[structopt(long)]
     list_languages: bool,
+
+    /// Run a challenge file
+    #[structopt(long, parse(from_os_str))]
+    challenge: Option<PathBuf>,
+
+    /// Learn touch typing, unlocking keys as you improve
+    #[structopt(long)]
+    lesson: bool,
+
+    /// Type through the text file a chunk at a time, continuing where the last run stopped
+    #[structopt(long)]
+    book: bool,
+
+    /// In book mode, type a paragraph at a time instead of a number of words
+    #[structopt(long, requires = "book")]
+    paragraphs: bool,
+
+    /// Type each line of the text as one word, instead of splitting it into words like prose
+    #[structopt(long)]
+    lines: bool,
+
+    /// Only use words made of these characters
+    #[structopt(long, value_name = "chars")]
+    allow: Option<String>,
+
+    /// Only use words containing at least one of these characters
+    #[structopt(long, value_name = "chars")]
+    require: Option<String>,
+
+    /// Only use words at least this long
+    #[structopt(long, value_name = "length")]
+    min_length: Option<usize>,
+
+    /// Only use words at most this long
+    #[structopt(long, value_name = "length")]
+    max_length: Option<usize>,
+
+    /// Draw words evenly, or as often as in real text
+    #[structopt(long, default_value = "uniform", value_name = "uniform|zipf")]
+    sampling: Sampling,
+
+    /// Only use this many of the most common words
+    #[structopt(long, value_name = "count")]
+    top: Option<num::NonZeroUsize>,
+
+    /// Never put the same word twice in a row
+    #[structopt(long)]
+    no_repeats: bool,
+
+    /// Generate pseudo-words or word sequences from the language with a Markov chain
+    #[structopt(long, value_name = "char|word")]
+    markov: Option<markov::Level>,
+
+    /// Number of letters or words the Markov chain looks back on
+    #[structopt(long, default_value = "2", value_name = "order")]
+    markov_order: usize,
+
+    /// Drill identifiers, operators, brackets, strings and numbers built from the language
+    #[structopt(long, conflicts_with = "markov")]
+    symbols: bool,
+
+    /// Type snippets of code from these source files or directories
+    #[structopt(
+        long,
+        parse(from_os_str),
+        number_of_values = 1,
+        value_name = "path",
+        conflicts_with = "contents"
+    )]
+    code: Vec<PathBuf>,
+
+    /// Most lines in a code snippet
+    #[structopt(long, default_value = "12", value_name = "lines")]
+    snippet_lines: usize,
+
+    /// Remove comments from code snippets
+    #[structopt(long)]
+    strip_comments: bool,
+
+    /// Type the output of a command
+    #[structopt(long, value_name = "command", conflicts_with_all = &["contents", "code"])]
+    command: Option<String>,
+
+    /// Type the output of a command named in the config
+    #[structopt(long, value_name = "name", conflicts_with_all = &["contents", "code", "command"])]
+    generator: Option<String>,
+
+    /// Seed the word generator, to get the same words every run
+    #[structopt(long)]
+    seed: Option<u64>,
 }
 
 impl Opt {
-    fn gen_contents(&self) -> Option<Vec<String>> {
+    fn gen_contents(&self, config: &Config, rng: &mut impl Rng) -> Result<Vec<String>, String> {
+        if !self.code.is_empty() {
+            return self.gen_code_contents(rng);
+        }
+        if let Some(generator) = self.generator(config)? {
+            return self.gen_command_contents(&generator, config);
+        }
+
+        // text files are learned from as prose, rather than as a word on each line
+        let text = self.contents.as_ref().or(self.language_file.as_ref());
+        if let (Some(level), Some(path)) = (self.markov, text) {
+            let words = self.text_words(path, config)?;
+            let words = self
+                .filter()
+                .apply(words.iter().map(|word| word.trim_end()).collect())
+                .map_err(|err| format!("Couldn't generate a test: {}.", err))?;
+            return self.gen_markov_contents(level, &[(words, 1)], rng);
+        }
+
         match &self.contents {
-            Some(path) => {
-                let lines: Vec<String> = if path.as_os_str() == "-" {
-                    std::io::stdin()
-                        .lock()
-                        .lines()
-                        .filter_map(Result::ok)
-                        .collect()
-                } else {
-                    let file = fs::File::open(path).expect("Error reading language file.");
-                    io::BufReader::new(file)
-                        .lines()
-                        .filter_map(Result::ok)
-                        .collect()
-                };
-
-                Some(lines.iter().map(String::from).collect())
-            }
+            Some(path) => self.text_words(path, config),
             None => {
-                let lang_name = self
-                    .language
-                    .clone()
-                    .unwrap_or_else(|| self.config().default_language);
-
-                let bytes: Vec<u8> = self
-                    .language_file
-                    .as_ref()
-                    .map(fs::read)
-                    .and_then(Result::ok)
-                    .or_else(|| fs::read(self.language_dir().join(&lang_name)).ok())
-                    .or_else(|| {
-                        Resources::get(&format!("language/{}", &lang_name))
-                            .map(|f| f.data.into_owned())
-                    })?;
-
-                let mut rng = thread_rng();
-
-                let mut language: Vec<&str> = str::from_utf8(&bytes)
-                    .expect("Language file had non-utf8 encoding.")
-                    .lines()
-                    .collect();
-                language.shuffle(&mut rng);
-
-                let mut contents: Vec<_> = language
-                    .into_iter()
-                    .cycle()
-                    .take(self.words.get())
-                    .map(ToOwned::to_owned)
-                    .collect();
-                contents.shuffle(&mut rng);
-
-                Some(contents)
+                let languages = self.selected_languages(config)?;
+                let mut pools = Vec::with_capacity(languages.len());
+                for (bytes, weight) in &languages {
+                    let words = self
+                        .filter()
+                        .apply(language_words(bytes))
+                        .map_err(|err| format!("Couldn't generate a test: {}.", err))?;
+                    pools.push((words, *weight));
+                }
+
+                if self.symbols {
+                    return symbols::generate(&pools, config.symbols, self.words.get(), rng)
+                        .map_err(|err| format!("Couldn't generate a test: {}.", err));
+                }
+                match self.markov {
+                    Some(level) => self.gen_markov_contents(level, &pools, rng),
+                    None => generate::mix(pools, &self.sampler(), self.words.get(), rng)
+                        .map_err(|err| format!("Couldn't generate a test: {}.", err)),
+                }
             }
         }
     }
 
+    /// Generate pseudo-words or word sequences with a Markov chain learned from weighted languages
+    fn gen_markov_contents(
+        &self,
+        level: markov::Level,
+        languages: &[(Vec<&str>, u32)],
+        rng: &mut impl Rng,
+    ) -> Result<Vec<String>, String> {
+        markov::generate(languages, level, self.markov_order, self.words.get(), rng)
+            .map_err(|err| format!("Couldn't generate a test: {}.", err))
+    }
+
+    /// Pick a snippet from the code files
+    fn gen_code_contents(&self, rng: &mut impl Rng) -> Result<Vec<String>, String> {
+        let files = code::source_files(&self.code)
+            .map_err(|err| format!("Couldn't find source files: {}", err))?;
+        let options = code::Options {
+            lines: self.snippet_lines,
+            strip_comments: self.strip_comments,
+        };
+        code::snippet(&files, options, rng)
+    }
+
+    /// The command to type the output of, if any
+    fn generator(&self, config: &Config) -> Result<Option<Generator>, String> {
+        match (&self.command, &self.generator) {
+            (Some(command), _) => Ok(Some(Generator::new(command.clone()))),
+            (None, Some(name)) => config
+                .generators
+                .get(name)
+                .cloned()
+                .map(Some)
+                .ok_or_else(|| format!("Couldn't find generator `{}` in the config.", name)),
+            (None, None) => Ok(None),
+        }
+    }
+
+    /// Run a command and read the words of its output
+    fn gen_command_contents(
+        &self,
+        generator: &Generator,
+        config: &Config,
+    ) -> Result<Vec<String>, String> {
+        let output = generator.run()?;
+        let words = self.tokenize(&output, false, config);
+        if words.is_empty() {
+            return Err(format!("`{}` didn't output any words.", generator.command));
+        }
+        Ok(words)
+    }
+
+    /// Split text into words, or into lines if asked to
+    fn tokenize(&self, text: &str, markdown: bool, config: &Config) -> Vec<String> {
+        if self.lines {
+            return text.lines().map(ToOwned::to_owned).collect();
+        }
+        prose::tokenize(text, markdown, config.prose)
+    }
+
+    /// Read the words of a text file, or of standard input if the path is `-`
+    fn text_words(&self, path: &Path, config: &Config) -> Result<Vec<String>, String> {
+        let text = if path.as_os_str() == "-" {
+            io::read_to_string(io::stdin())
+        } else {
+            fs::read_to_string(path)
+        }
+        .map_err(|err| format!("Couldn't read {}: {}", path.display(), err))?;
+
+        let markdown = path
+            .extension()
+            .is_some_and(|ext| ext == "md" || ext == "markdown");
+        let words = self.tokenize(&text, markdown, config);
+        if words.is_empty() {
+            return Err(format!("There are no words in {}.", path.display()));
+        }
+        Ok(words)
+    }
+
+    /// How much of a book each test covers
+    fn chunk(&self) -> Chunk {
+        if self.paragraphs {
+            Chunk::Paragraph
+        } else {
+            Chunk::Words(self.words.get())
+        }
+    }
+
+    /// Generate lesson words from the selected languages
+    fn gen_lesson_contents(
+        &self,
+        lesson: &Lesson,
+        config: &Config,
+        rng: &mut impl Rng,
+    ) -> Result<Vec<String>, String> {
+        let languages = self.selected_languages(config)?;
+        let languages: Vec<(&str, u32)> = languages
+            .iter()
+            .map(|(bytes, weight)| {
+                let language = str::from_utf8(bytes).expect("Language file had non-utf8 encoding.");
+                (language, *weight)
+            })
+            .collect();
+        lesson.words(&languages, self.words.get(), rng)
+    }
+
+    /// Contents of the selected languages, along with their weights
+    fn selected_languages(&self, config: &Config) -> Result<Vec<(Vec<u8>, u32)>, String> {
+        if let Some(bytes) = self
+            .language_file
+            .as_ref()
+            .map(fs::read)
+            .and_then(Result::ok)
+        {
+            return Ok(vec![(bytes, 1)]);
+        }
+
+        let languages = if self.language.is_empty() {
+            vec![WeightedLanguage {
+                name: config.default_language.clone(),
+                weight: 1,
+            }]
+        } else {
+            self.language.clone()
+        };
+        languages
+            .iter()
+            .map(|language| {
+                let bytes = self
+                    .language_bytes(&language.name)
+                    .ok_or_else(|| missing_language(&language.name))?;
+                Ok((bytes, language.weight))
+            })
+            .collect()
+    }
+
+    /// Limits on the words sampled from a language
+    fn filter(&self) -> Filter {
+        Filter {
+            allowed: self.allow.clone(),
+            required: self.require.clone(),
+            min_length: self.min_length,
+            max_length: self.max_length,
+        }
+    }
+
+    /// Load the challenge file, if one was given
+    fn challenge(&self) -> Option<Result<Challenge, challenge::Error>> {
+        let path = self.challenge.as_ref().or_else(|| {
+            self.contents
+                .as_ref()
+                .filter(|path| path.extension().is_some_and(|ext| ext == "toml"))
+        })?;
+
+        Some(Challenge::from_file(path))
+    }
+
+    /// Generate test contents exactly as described by a challenge
+    fn gen_challenge_contents(&self, challenge: &Challenge) -> Result<Vec<String>, String> {
+        match (&challenge.text, &challenge.language, challenge.seed) {
+            (Some(text), _, _) => Ok(text
+                .split_whitespace()
+                .take(challenge.word_count())
+                .map(ToOwned::to_owned)
+                .collect()),
+            (None, Some(lang_name), Some(seed)) => Ok(sample_words(
+                language_words(
+                    &self
+                        .language_bytes(lang_name)
+                        .ok_or_else(|| missing_language(lang_name))?,
+                ),
+                challenge.word_count(),
+                &mut StdRng::seed_from_u64(seed),
+            )),
+            _ => unreachable!("challenges are validated when loaded"),
+        }
+    }
+
+    /// Contents of a named language, from the config directory or built in
+    fn language_bytes(&self, lang_name: &str) -> Option<Vec<u8>> {
+        fs::read(self.language_dir().join(lang_name))
+            .ok()
+            .or_else(|| {
+                Resources::get(&format!("language/{}", lang_name)).map(|f| f.data.into_owned())
+            })
+    }
 
     /// Configuration
     fn config(&self) -> Config {
@@ -127,6 +415,22 @@ impl Opt {
         .unwrap_or_default()
     }
 
+    /// Load a keyboard layout, from the config directory or built in
+    fn layout(&self, name: &str) -> Result<Layout, String> {
+        let path = self
+            .config_dir()
+            .join("layout")
+            .join(format!("{}.toml", name));
+        let layout = match fs::read(path) {
+            Ok(bytes) => str::from_utf8(&bytes)
+                .map_err(|err| err.to_string())
+                .and_then(Layout::from_toml),
+            Err(_) => Layout::builtin(name)
+                .ok_or_else(|| format!("Couldn't find keyboard layout `{}`.", name))?,
+        };
+        layout.map_err(|err| format!("Keyboard layout `{}` was ill-formed: {}", name, err))
+    }
+
     /// Installed languages under config directory
     fn languages(&self) -> io::Result<Vec<OsString>> {
         Ok(self
@@ -144,15 +448,72 @@ impl Opt {
             .join("ttyper")
     }
 
+    /// How to draw words from the language
+    fn sampler(&self) -> Sampler {
+        Sampler {
+            mode: self.sampling,
+            top: self.top,
+            no_repeats: self.no_repeats,
+        }
+    }
+
+    /// Random number generator for test contents
+    fn rng(&self) -> StdRng {
+        match self.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        }
+    }
+
+    /// Lesson progress file under config directory
+    fn progress_path(&self) -> PathBuf {
+        self.config_dir().join("lessons.toml")
+    }
+
+    /// Book bookmarks file under config directory
+    fn bookmarks_path(&self) -> PathBuf {
+        self.config_dir().join("bookmarks.toml")
+    }
+
     /// Language directory under config directory
     fn language_dir(&self) -> PathBuf {
         self.config_dir().join("language")
     }
 }
 
+/// Words of a language file, one per line
+fn language_words(bytes: &[u8]) -> Vec<&str> {
+    str::from_utf8(bytes)
+        .expect("Language file had non-utf8 encoding.")
+        .lines()
+        .collect()
+}
+
+fn missing_language(lang_name: &str) -> String {
+    format!(
+        "Couldn't find language `{}`. Make sure the specified language actually exists.",
+        lang_name
+    )
+}
+
+/// How long to wait for input before a running test needs updating anyway
+fn test_timeout(test: &Test, idle_timeout: Option<Duration>) -> Option<Duration> {
+    if test.paused() {
+        return None;
+    }
+    let last_activity = test.last_activity()?;
+
+    let time_limit = test
+        .time_remaining()
+        .map(|remaining| remaining.min(TICK_RATE));
+    let idle = idle_timeout.map(|idle| idle.saturating_sub(last_activity.elapsed()));
+
+    time_limit.into_iter().chain(idle).min()
+}
+
 enum State {
     Test(Test),
-    Results(Results),
+    Results(Box<Results>),
 }
 
 impl State {
@@ -160,16 +521,40 @@ impl State {
         &self,
         terminal: &mut Terminal<B>,
         config: &Config,
+        layout: &Layout,
+        book: Option<&RefCell<Book>>,
+        heatmap: Heatmap,
+        show_fingers: bool,
     ) -> crossterm::Result<()> {
         match self {
             State::Test(test) => {
+                let book = book.map(|book| {
+                    let book = book.borrow();
+                    (book.position(), book.word_count())
+                });
                 terminal.draw(|f| {
-                    f.render_widget(config.theme.apply_to(test), f.size());
+                    f.render_widget(
+                        config.theme.apply_to(TestScreen {
+                            test,
+                            keyboard: config.show_keyboard.then_some(layout),
+                            book,
+                        }),
+                        f.size(),
+                    );
                 })?;
             }
             State::Results(results) => {
                 terminal.draw(|f| {
-                    f.render_widget(config.theme.apply_to(results), f.size());
+                    f.render_widget(
+                        config.theme.apply_to(ResultsScreen {
+                            results,
+                            keys: &config.keys,
+                            keyboard: layout,
+                            heatmap,
+                            show_fingers,
+                        }),
+                        f.size(),
+                    );
                 })?;
             }
         }
@@ -196,6 +581,148 @@ fn main() -> crossterm::Result<()> {
         return Ok(());
     }
 
+    let challenge = opt.challenge().transpose().unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        process::exit(1);
+    });
+    if opt.debug {
+        dbg!(&challenge);
+    }
+
+    let load_layout = |name: &str| {
+        opt.layout(name).unwrap_or_else(|err| {
+            eprintln!("{}", err);
+            process::exit(1);
+        })
+    };
+    // the layout being practiced
+    let layout = load_layout(&config.layout);
+    // keys are translated from the layout the system uses into the one being practiced
+    let remap_from = config.remap_from.as_deref().map(load_layout);
+    let mut heatmap = Heatmap::default();
+    let mut show_fingers = false;
+
+    if let Err(err) = config.keys.validate() {
+        eprintln!("Invalid key bindings: {}", err);
+        process::exit(1);
+    }
+
+    let mut progress = Progress::default();
+    let lesson = if opt.lesson {
+        if challenge.is_some() {
+            eprintln!("Lessons can't be combined with a challenge.");
+            process::exit(1);
+        }
+        progress = Progress::load(&opt.progress_path()).unwrap_or_else(|err| {
+            eprintln!("{}", err);
+            process::exit(1);
+        });
+        let unlocked = progress.unlocked.get(&config.layout).copied();
+        Some(RefCell::new(Lesson::new(&layout, unlocked)))
+    } else {
+        None
+    };
+
+    let mut bookmarks = Bookmarks::default();
+    let book = if opt.book {
+        if challenge.is_some() || opt.lesson {
+            eprintln!("Books can't be combined with a challenge or lesson.");
+            process::exit(1);
+        }
+        let Some(path) = &opt.contents else {
+            eprintln!("Book mode needs a text file to read.");
+            process::exit(1);
+        };
+        bookmarks = Bookmarks::load(&opt.bookmarks_path()).unwrap_or_else(|err| {
+            eprintln!("{}", err);
+            process::exit(1);
+        });
+        let words = opt.text_words(path, &config).unwrap_or_else(|err| {
+            eprintln!("{}", err);
+            process::exit(1);
+        });
+        Some(RefCell::new(Book::new(words, &bookmarks)))
+    } else {
+        None
+    };
+
+    // shared between tests, so that a seeded run gives a new but reproducible text each test
+    let rng = RefCell::new(opt.rng());
+    let new_test = || -> Result<Test, String> {
+        let rng = &mut *rng.borrow_mut();
+        let contents = match (&challenge, &lesson, &book) {
+            (Some(challenge), _, _) => opt.gen_challenge_contents(challenge),
+            (None, Some(lesson), _) => opt.gen_lesson_contents(&lesson.borrow(), &config, rng),
+            (None, None, Some(book)) => Ok(book.borrow_mut().next_chunk(opt.chunk())),
+            (None, None, None) => opt.gen_contents(&config, rng),
+        }?;
+
+        let mut test = Test::new(contents);
+        test.matching = config.matching;
+        if let Some(challenge) = &challenge {
+            test.matching = challenge.matching;
+            test.strict = challenge.strict;
+            test.time_limit = challenge.time_limit();
+        }
+        Ok(test)
+    };
+    let first_test = new_test().unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        process::exit(1);
+    });
+
+    // challenge contents are reproducible, so the hash only needs computing once
+    let challenge_info = challenge.as_ref().map(|challenge| {
+        // challenges are generated the same way every time, so this can't fail after the first
+        let test = new_test().unwrap();
+        let contents: Vec<String> = test.words.into_iter().map(|w| w.text).collect();
+        ChallengeInfo {
+            hash: challenge.hash(&contents),
+            target_wpm: challenge.target_wpm,
+        }
+    });
+    let mut results_of = |test: &Test| {
+        let mut results = Results::new(test, &layout);
+        results.challenge = challenge_info.clone();
+        if let Some(lesson) = &lesson {
+            let mut lesson = lesson.borrow_mut();
+            let mut info = lesson.record(&results);
+            if info.unlocked.is_some() {
+                progress
+                    .unlocked
+                    .insert(config.layout.clone(), lesson.unlocked_count());
+                info.save_error = progress
+                    .save(&opt.progress_path())
+                    .err()
+                    .map(|err| err.to_string());
+            }
+            results.lesson = Some(info);
+        }
+        if let Some(book) = &book {
+            let mut book = book.borrow_mut();
+            let mut info = book.record(&results);
+            if results.complete {
+                bookmarks
+                    .positions
+                    .insert(book.hash().to_owned(), book.position());
+                info.save_error = bookmarks
+                    .save(&opt.bookmarks_path())
+                    .err()
+                    .map(|err| err.to_string());
+            }
+            results.book = Some(info);
+        }
+        Box::new(results)
+    };
+
+    if let Some((action, chord)) = config.keys.prompt_conflict(|c| first_test.contains_char(c)) {
+        eprintln!(
+            "The `{}` key binding `{}` conflicts with a character in the test. Choose another key.",
+            action, chord
+        );
+        process::exit(1);
+    }
+
     let backend = CrosstermBackend::new(io::stdout());
     let mut terminal = Terminal::new(backend)?;
 
@@ -206,67 +733,165 @@ fn main() -> crossterm::Result<()> {
         cursor::SavePosition,
         terminal::EnterAlternateScreen,
     )?;
+    if config.pause_on_focus_loss {
+        execute!(io::stdout(), event::EnableFocusChange)?;
+    }
+    // without bracketed paste, pasted text would arrive as ordinary keypresses
+    execute!(io::stdout(), event::EnableBracketedPaste)?;
+    // key releases let us measure how long keys are held, and tell auto-repeat apart
+    let keyboard_enhanced = terminal::supports_keyboard_enhancement().unwrap_or(false);
+    if keyboard_enhanced {
+        execute!(
+            io::stdout(),
+            event::PushKeyboardEnhancementFlags(
+                event::KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES
+                    | event::KeyboardEnhancementFlags::REPORT_EVENT_TYPES
+            )
+        )?;
+    }
     terminal.clear()?;
 
-    let mut state = State::Test(Test::new(opt.gen_contents().expect(
-        "Couldn't get test contents. Make sure the specified language actually exists.",
-    )));
-
-    state.render_into(&mut terminal, &config)?;
+    let idle_timeout = Some(Duration::from_secs(config.idle_timeout)).filter(|d| !d.is_zero());
+    let mut state = State::Test(first_test);
+    // whether the current pause was caused by losing focus, and should end when it returns
+    let mut paused_by_focus = false;
+    // a failure to generate a later test, reported once the terminal is restored
+    let mut error = None;
+
+    state.render_into(
+        &mut terminal,
+        &config,
+        &layout,
+        book.as_ref(),
+        heatmap,
+        show_fingers,
+    )?;
     loop {
+        // wake up for time limits and idle detection, even without input
+        if let State::Test(ref mut test) = state {
+            if let Some(timeout) = test_timeout(test, idle_timeout) {
+                if !event::poll(timeout)? {
+                    match (test.last_activity(), idle_timeout) {
+                        (Some(last_activity), Some(idle)) if last_activity.elapsed() >= idle => {
+                            // idle time is left out of the results entirely
+                            test.pause_at(last_activity);
+                        }
+                        _ => {
+                            if test.time_remaining() == Some(Duration::ZERO) {
+                                test.complete = true;
+                                state = State::Results(results_of(test));
+                            }
+                        }
+                    }
+                    state.render_into(
+                        &mut terminal,
+                        &config,
+                        &layout,
+                        book.as_ref(),
+                        heatmap,
+                        show_fingers,
+                    )?;
+                    continue;
+                }
+            }
+        }
+
         let event = event::read()?;
 
-        // handle exit controls
         match event {
-            Event::Key(KeyEvent {
-                code: KeyCode::Char('c'),
-                modifiers: KeyModifiers::CONTROL,
-                ..
-            }) => break,
-            Event::Key(KeyEvent {
-                code: KeyCode::Esc,
-                modifiers: KeyModifiers::NONE,
-                ..
-            }) => match state {
-                State::Test(ref test) => {
-                    state = State::Results(Results::from(test));
+            Event::FocusLost if config.pause_on_focus_loss => {
+                if let State::Test(ref mut test) = state {
+                    if !test.paused() {
+                        test.pause();
+                        paused_by_focus = true;
+                    }
                 }
-                State::Results(_) => break,
-            },
-            _ => {}
-        }
-
-        match state {
-            State::Test(ref mut test) => {
-                if let Event::Key(key) = event {
-                    test.handle_key(key);
-                    if test.complete {
-                        state = State::Results(Results::from(&*test));
+            }
+            Event::FocusGained if config.pause_on_focus_loss => {
+                if let State::Test(ref mut test) = state {
+                    if paused_by_focus {
+                        test.resume();
                     }
                 }
+                paused_by_focus = false;
             }
-            State::Results(_) => match event {
-                Event::Key(KeyEvent {
-                    code: KeyCode::Char('r'),
-                    modifiers: KeyModifiers::NONE,
-                    ..
-                }) => {
-                    state = State::Test(Test::new(opt.gen_contents().expect(
-                            "Couldn't get test contents. Make sure the specified language actually exists.",
-                        )));
+            Event::Paste(_) => {
+                if let State::Test(ref mut test) = state {
+                    test.handle_paste();
                 }
-                Event::Key(KeyEvent {
-                    code: KeyCode::Char('q'),
-                    modifiers: KeyModifiers::NONE,
-                    ..
-                }) => break,
-                _ => {}
+            }
+            Event::Key(key) => match state {
+                State::Test(ref mut test) => {
+                    if key.kind != KeyEventKind::Release {
+                        paused_by_focus = false;
+                    }
+                    match config.keys.test_action(&key, |c| test.contains_char(c)) {
+                        Some(Action::Quit) => break,
+                        Some(Action::Restart) => match new_test() {
+                            Ok(test) => state = State::Test(test),
+                            Err(err) => {
+                                error = Some(err);
+                                break;
+                            }
+                        },
+                        Some(Action::RetrySame) => test.reset(),
+                        Some(Action::Abort) => state = State::Results(results_of(test)),
+                        Some(Action::DeleteWord) => test.delete_word(key),
+                        Some(Action::DeleteLine) => test.delete_line(key),
+                        Some(Action::Pause) => {
+                            if test.paused() {
+                                test.resume();
+                            } else {
+                                test.pause();
+                            }
+                        }
+                        Some(Action::ToggleHeatmap | Action::ToggleFingers) => {}
+                        None => {
+                            let key = match &remap_from {
+                                Some(from) => from.remap(&layout, key),
+                                None => key,
+                            };
+                            test.handle_key(key);
+                            if test.complete {
+                                state = State::Results(results_of(test));
+                            }
+                        }
+                    }
+                }
+                State::Results(_) => match config.keys.results_action(&key) {
+                    Some(Action::Quit) => break,
+                    Some(Action::Restart) => match new_test() {
+                        Ok(test) => state = State::Test(test),
+                        Err(err) => {
+                            error = Some(err);
+                            break;
+                        }
+                    },
+                    Some(Action::ToggleHeatmap) => heatmap = heatmap.toggled(),
+                    Some(Action::ToggleFingers) => show_fingers = !show_fingers,
+                    _ => {}
+                },
             },
+            _ => {}
         }
 
-        state.render_into(&mut terminal, &config)?;
+        state.render_into(
+            &mut terminal,
+            &config,
+            &layout,
+            book.as_ref(),
+            heatmap,
+            show_fingers,
+        )?;
     }
 
+    if keyboard_enhanced {
+        execute!(io::stdout(), event::PopKeyboardEnhancementFlags)?;
+    }
+    execute!(io::stdout(), event::DisableBracketedPaste)?;
+    if config.pause_on_focus_loss {
+        execute!(io::stdout(), event::DisableFocusChange)?;
+    }
     terminal::disable_raw_mode()?;
     execute!(
         io::stdout(),
@@ -275,5 +900,9 @@ fn main() -> crossterm::Result<()> {
         terminal::LeaveAlternateScreen,
     )?;
 
+    if let Some(err) = error {
+        eprintln!("{}", err);
+        process::exit(1);
+    }
     Ok(())
 }
@@ -0,0 +1,27 @@
+use serde::{de::DeserializeOwned, Serialize};
+use std::{fs, io, path::Path};
+
+/// State kept between runs in a TOML file, such as lesson progress and bookmarks.
+pub trait StateFile: Default + DeserializeOwned + Serialize {
+    /// What the state is called in error messages.
+    const NAME: &'static str;
+
+    /// Load the state, starting afresh if there isn't any yet.
+    fn load(path: &Path) -> Result<Self, String> {
+        match fs::read_to_string(path) {
+            Ok(source) => toml::from_str(&source)
+                .map_err(|err| format!("Couldn't parse {}: {}", Self::NAME, err)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(format!("Couldn't read {}: {}", Self::NAME, err)),
+        }
+    }
+
+    fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let source =
+            toml::to_string(self).unwrap_or_else(|_| panic!("Couldn't serialize {}.", Self::NAME));
+        fs::write(path, source)
+    }
+}
@@ -0,0 +1,140 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use unicode_normalization::{char::is_combining_mark, UnicodeNormalization};
+
+/// Which differences between typed and expected text are forgiven.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct MatchPolicy {
+    /// Treat upper and lower case letters as equal.
+    pub ignore_case: bool,
+    /// Treat accented letters as equal to their base letters, e.g. "a" matches "á".
+    pub fold_diacritics: bool,
+    /// Accept spelled out umlauts and eszetts, e.g. "ue" matches "ü".
+    pub transliterate: bool,
+    /// Treat all quote variants as equal, and likewise all dash variants.
+    pub fold_punctuation: bool,
+}
+
+impl MatchPolicy {
+    pub fn is_exact(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Canonical form of a string under this policy, as decomposed characters.
+    pub fn fold(&self, string: &str) -> Vec<char> {
+        let transliterated: String = if self.transliterate {
+            string.chars().fold(String::new(), |mut s, c| {
+                match c {
+                    'ä' => s.push_str("ae"),
+                    'ö' => s.push_str("oe"),
+                    'ü' => s.push_str("ue"),
+                    'Ä' => s.push_str("Ae"),
+                    'Ö' => s.push_str("Oe"),
+                    'Ü' => s.push_str("Ue"),
+                    'ß' => s.push_str("ss"),
+                    'ẞ' => s.push_str("SS"),
+                    c => s.push(c),
+                }
+                s
+            })
+        } else {
+            string.to_owned()
+        };
+
+        let mut folded = Vec::new();
+        for c in transliterated.nfd() {
+            if self.fold_diacritics && is_combining_mark(c) {
+                continue;
+            }
+            if self.ignore_case {
+                folded.extend(c.to_lowercase().map(|c| self.fold_char(c)));
+            } else {
+                folded.push(self.fold_char(c));
+            }
+        }
+        folded
+    }
+
+    fn fold_char(&self, c: char) -> char {
+        if !self.fold_punctuation {
+            return c;
+        }
+        match c {
+            '‘' | '’' | '‚' | '‛' | '′' | '‹' | '›' | '`' | '´' => '\'',
+            '“' | '”' | '„' | '‟' | '″' | '«' | '»' => '"',
+            '‐' | '‑' | '‒' | '–' | '—' | '―' | '−' | '⁃' => '-',
+            c => c,
+        }
+    }
+
+    /// Whether `typed` matches the start of `expected`.
+    pub fn is_prefix(&self, expected: &str, typed: &str) -> bool {
+        self.fold(expected).starts_with(&self.fold(typed))
+    }
+
+    /// Whether `typed` matches all of `expected`.
+    pub fn matches(&self, expected: &str, typed: &str) -> bool {
+        self.fold(expected) == self.fold(typed)
+    }
+}
+
+impl fmt::Display for MatchPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_exact() {
+            return f.write_str("exact");
+        }
+
+        let options: Vec<&str> = [
+            (self.ignore_case, "ignore case"),
+            (self.fold_diacritics, "ignore accents"),
+            (self.transliterate, "spelled out umlauts"),
+            (self.fold_punctuation, "any quotes and dashes"),
+        ]
+        .into_iter()
+        .filter_map(|(enabled, name)| enabled.then_some(name))
+        .collect();
+        f.write_str(&options.join(", "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exact_policy_only_normalizes() {
+        let policy = MatchPolicy::default();
+        assert!(policy.matches("für", "fu\u{308}r"));
+        assert!(!policy.matches("für", "fur"));
+        assert!(!policy.matches("Für", "für"));
+        assert_eq!(policy.to_string(), "exact");
+    }
+
+    #[test]
+    fn folds_text() {
+        let policy = MatchPolicy {
+            ignore_case: true,
+            fold_diacritics: true,
+            ..Default::default()
+        };
+        assert!(policy.matches("Está", "esta"));
+        assert!(policy.is_prefix("Ñandú", "nan"));
+        assert!(!policy.matches("für", "fuer"));
+
+        let policy = MatchPolicy {
+            transliterate: true,
+            ..Default::default()
+        };
+        assert!(policy.matches("Grüße", "Gruesse"));
+        assert!(policy.matches("Grüße", "Grüße"));
+        assert!(policy.is_prefix("über", "u"));
+
+        let policy = MatchPolicy {
+            fold_punctuation: true,
+            ..Default::default()
+        };
+        assert!(policy.matches("„it’s—fine“", "\"it's-fine\""));
+        assert_eq!(policy.to_string(), "any quotes and dashes");
+    }
+}
@@ -1,13 +1,19 @@
+pub mod matching;
 pub mod results;
 
-use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use crossterm::event::{KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use matching::MatchPolicy;
 use std::fmt;
-use std::time::Instant;
+use std::time::{Duration, Instant};
+use unicode_normalization::UnicodeNormalization;
+use unicode_segmentation::UnicodeSegmentation;
 
 pub struct TestEvent {
     pub time: Instant,
     pub key: KeyEvent,
     pub correct: Option<bool>,
+    /// When the key was let go, if the terminal reports releases.
+    pub released: Option<Instant>,
 }
 
 impl fmt::Debug for TestEvent {
@@ -24,23 +30,97 @@ pub struct TestWord {
     pub text: String,
     pub progress: String,
     pub events: Vec<TestEvent>,
+    /// Whether the word is the last of a paragraph, marked by a trailing newline in its source.
+    pub ends_paragraph: bool,
 }
 
 impl From<String> for TestWord {
     fn from(string: String) -> Self {
         TestWord {
-            text: string,
+            text: string.trim_end_matches('\n').nfc().collect(),
             progress: String::new(),
             events: Vec::new(),
+            ends_paragraph: string.ends_with('\n'),
         }
     }
 }
 
+impl TestWord {
+    /// Whether what has been typed so far matches the start of the text under the policy.
+    ///
+    /// This compares decomposed characters, so that a base letter typed on its way to becoming
+    /// an accented one counts as correct.
+    pub fn progress_correct(&self, policy: &MatchPolicy) -> bool {
+        policy.is_prefix(&self.text, &self.progress)
+    }
+
+    /// Whether the word has been typed in full.
+    pub fn typed_correctly(&self, policy: &MatchPolicy) -> bool {
+        policy.matches(&self.text, &self.progress)
+    }
+
+    /// Byte length of the start of the text covered by the progress, rounded up to a grapheme.
+    pub fn typed_len(&self, policy: &MatchPolicy) -> usize {
+        let typed = policy.fold(&self.progress).len();
+        if typed == 0 {
+            return 0;
+        }
+
+        let mut folded = 0;
+        for (i, grapheme) in self.text.grapheme_indices(true) {
+            folded += policy.fold(grapheme).len();
+            if folded >= typed {
+                return i + grapheme.len();
+            }
+        }
+        self.text.len()
+    }
+
+    /// The grapheme of the text expected to be typed next.
+    fn next_grapheme(&self, policy: &MatchPolicy) -> Option<&str> {
+        self.text[self.typed_len(policy)..].graphemes(true).next()
+    }
+
+    fn push(&mut self, c: char) {
+        self.progress.push(c);
+        // typed input may be decomposed, e.g. by dead keys
+        self.progress = self.progress.nfc().collect();
+    }
+
+    fn pop(&mut self) {
+        let last = self.progress.grapheme_indices(true).next_back();
+        self.progress.truncate(last.map_or(0, |(i, _)| i));
+    }
+}
+
+/// A span of time left out of the test's timing.
+#[derive(Debug, Clone, Copy)]
+pub struct Pause {
+    pub start: Instant,
+    /// `None` while the test is still paused.
+    pub end: Option<Instant>,
+}
+
+impl Pause {
+    /// How much of the time between `from` and `to` this pause covers.
+    pub fn overlap(&self, from: Instant, to: Instant) -> Duration {
+        let start = self.start.max(from);
+        let end = self.end.unwrap_or(to).min(to);
+        end.saturating_duration_since(start)
+    }
+}
+
 #[derive(Debug)]
 pub struct Test {
     pub words: Vec<TestWord>,
     pub current_word: usize,
     pub complete: bool,
+    pub strict: bool,
+    pub time_limit: Option<Duration>,
+    pub matching: MatchPolicy,
+    pub pauses: Vec<Pause>,
+    /// Whether text was pasted into the test.
+    pub pasted: bool,
 }
 
 impl Test {
@@ -49,27 +129,153 @@ impl Test {
             words: words.into_iter().map(TestWord::from).collect(),
             current_word: 0,
             complete: false,
+            strict: false,
+            time_limit: None,
+            matching: MatchPolicy::default(),
+            pauses: Vec::new(),
+            pasted: false,
+        }
+    }
+
+    /// Start the test over with the same words.
+    pub fn reset(&mut self) {
+        for word in &mut self.words {
+            word.progress.clear();
+            word.events.clear();
+        }
+        self.current_word = 0;
+        self.complete = false;
+        self.pauses.clear();
+        self.pasted = false;
+    }
+
+    /// Pasted text isn't typed, but the attempt is remembered.
+    pub fn handle_paste(&mut self) {
+        self.pasted = true;
+    }
+
+    /// The character expected to be typed next, unless there's a mistake to fix first.
+    pub fn next_char(&self) -> Option<char> {
+        let word = &self.words[self.current_word];
+        if !word.progress_correct(&self.matching) {
+            return None;
+        }
+        match word.next_grapheme(&self.matching) {
+            Some(grapheme) => grapheme.chars().next(),
+            None => Some(' '),
+        }
+    }
+
+    /// Time of the latest keypress, if there has been one.
+    pub fn last_event_time(&self) -> Option<Instant> {
+        self.words
+            .iter()
+            .flat_map(|w| w.events.iter())
+            .map(|e| e.time)
+            .max()
+    }
+
+    /// Time of the latest keypress or resumption, from which idle time is measured, if the test
+    /// has started.
+    pub fn last_activity(&self) -> Option<Instant> {
+        let last_event = self.last_event_time()?;
+        let resumed = self.pauses.last().and_then(|p| p.end);
+        Some(resumed.map_or(last_event, |resumed| resumed.max(last_event)))
+    }
+
+    pub fn paused(&self) -> bool {
+        self.pauses.last().is_some_and(|p| p.end.is_none())
+    }
+
+    /// Pause the test, counting it as paused since `start`, or since the last pause ended if
+    /// that was later.
+    pub fn pause_at(&mut self, start: Instant) {
+        if !self.paused() {
+            // pauses never overlap, so that no time is left out twice
+            let start = match self.pauses.last().and_then(|p| p.end) {
+                Some(end) => start.max(end),
+                None => start,
+            };
+            self.pauses.push(Pause { start, end: None });
+        }
+    }
+
+    pub fn pause(&mut self) {
+        self.pause_at(Instant::now());
+    }
+
+    pub fn resume(&mut self) {
+        if let Some(pause) = self.pauses.last_mut().filter(|p| p.end.is_none()) {
+            pause.end = Some(Instant::now());
         }
     }
 
+    /// Total time spent paused between `from` and `to`.
+    pub fn paused_between(&self, from: Instant, to: Instant) -> Duration {
+        self.pauses.iter().map(|p| p.overlap(from, to)).sum()
+    }
+
+    /// Time between `from` and `to`, excluding pauses.
+    pub fn active_between(&self, from: Instant, to: Instant) -> Duration {
+        to.saturating_duration_since(from)
+            .saturating_sub(self.paused_between(from, to))
+    }
+
+    /// Time spent typing since the first keypress, excluding pauses.
+    pub fn active_time(&self) -> Duration {
+        match self.start_time() {
+            Some(start) => self.active_between(start, Instant::now()),
+            None => Duration::ZERO,
+        }
+    }
+
+    /// Time of the first keypress, if there has been one.
+    pub fn start_time(&self) -> Option<Instant> {
+        self.words
+            .iter()
+            .flat_map(|w| w.events.iter())
+            .map(|e| e.time)
+            .min()
+    }
+
+    /// Time left before the time limit is reached, if there is one.
+    pub fn time_remaining(&self) -> Option<Duration> {
+        let limit = self.time_limit?;
+        Some(limit.saturating_sub(self.active_time()))
+    }
+
     pub fn handle_key(&mut self, key: KeyEvent) {
+        if key.kind == KeyEventKind::Release {
+            self.handle_release(key);
+            return;
+        }
+
+        // typing resumes a paused test
+        self.resume();
+
         let word = &mut self.words[self.current_word];
         match key.code {
             KeyCode::Char(' ') | KeyCode::Enter => {
-                if word.text.chars().nth(word.progress.len()) == Some(' ') {
+                if word.next_grapheme(&self.matching) == Some(" ") {
                     word.progress.push(' ');
                     word.events.push(TestEvent {
                         time: Instant::now(),
                         correct: Some(true),
                         key,
+                        released: None,
                     })
                 } else if !word.progress.is_empty() || word.text.is_empty() {
+                    let correct = word.typed_correctly(&self.matching);
                     word.events.push(TestEvent {
                         time: Instant::now(),
-                        correct: Some(word.text == word.progress),
+                        correct: Some(correct),
                         key,
+                        released: None,
                     });
-                    self.next_word();
+                    // strict tests don't move on until the word is fixed
+                    if correct || !self.strict {
+                        self.next_word();
+                    }
                 }
             }
             KeyCode::Backspace => {
@@ -78,35 +284,27 @@ impl Test {
                 } else {
                     word.events.push(TestEvent {
                         time: Instant::now(),
-                        correct: Some(!word.text.starts_with(&word.progress[..])),
+                        correct: Some(!word.progress_correct(&self.matching)),
                         key,
+                        released: None,
                     });
-                    word.progress.pop();
+                    word.pop();
                 }
             }
-            // CTRL-BackSpace
-            KeyCode::Char('h') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                if self.words[self.current_word].progress.is_empty() {
-                    self.last_word();
-                }
-
-                let word = &mut self.words[self.current_word];
-
-                word.events.push(TestEvent {
-                    time: Instant::now(),
-                    correct: None,
-                    key,
-                });
-                word.progress.clear();
-            }
+            // control and alt chords aren't typing, but both together may be AltGr
+            KeyCode::Char(_)
+                if key.modifiers.contains(KeyModifiers::CONTROL)
+                    != key.modifiers.contains(KeyModifiers::ALT) => {}
             KeyCode::Char(c) => {
-                word.progress.push(c);
+                word.push(c);
                 word.events.push(TestEvent {
                     time: Instant::now(),
-                    correct: Some(word.text.starts_with(&word.progress[..])),
+                    correct: Some(word.progress_correct(&self.matching)),
                     key,
+                    released: None,
                 });
-                if word.progress == word.text && self.current_word == self.words.len() - 1 {
+                if word.typed_correctly(&self.matching) && self.current_word == self.words.len() - 1
+                {
                     self.complete = true;
                     self.current_word = 0;
                 }
@@ -115,6 +313,124 @@ impl Test {
         };
     }
 
+    /// Record when the most recent press of a key ended.
+    fn handle_release(&mut self, key: KeyEvent) {
+        let same_key = |a: KeyCode, b: KeyCode| match (a, b) {
+            // shift may be let go before the key itself
+            (KeyCode::Char(a), KeyCode::Char(b)) => a.to_lowercase().eq(b.to_lowercase()),
+            (a, b) => a == b,
+        };
+
+        let now = Instant::now();
+        if let Some(event) = self
+            .words
+            .iter_mut()
+            .flat_map(|w| w.events.iter_mut())
+            .filter(|e| e.key.kind == KeyEventKind::Press && same_key(e.key.code, key.code))
+            .max_by_key(|e| e.time)
+            .filter(|e| e.released.is_none())
+        {
+            event.released = Some(now);
+        }
+    }
+
+    /// Delete back to the last space in the current word, or in the previous one if nothing
+    /// has been typed yet.
+    pub fn delete_word(&mut self, key: KeyEvent) {
+        self.delete_back(key, |progress| {
+            progress
+                .trim_end_matches(' ')
+                .rfind(' ')
+                .map_or(0, |i| i + 1)
+        });
+    }
+
+    /// Clear back to the start of the line, which follows the last word ending a paragraph, or
+    /// the previous line if nothing has been typed on this one yet. Word lists have no lines, so
+    /// only the current word is cleared.
+    ///
+    /// Like backspace, this counts as correct only when it removes a mistake.
+    pub fn delete_line(&mut self, key: KeyEvent) {
+        self.resume();
+
+        if self.has_lines()
+            && self.words[self.current_word].progress.is_empty()
+            && self.line_start(self.current_word) == self.current_word
+        {
+            self.last_word();
+        }
+
+        let start = self.line_start(self.current_word);
+        let line = start..=self.current_word;
+        if self.words[line.clone()]
+            .iter()
+            .all(|w| w.progress.is_empty())
+        {
+            return;
+        }
+
+        let mistaken = self.words[line.clone()]
+            .iter()
+            .any(|w| !w.progress_correct(&self.matching));
+        self.words[self.current_word].events.push(TestEvent {
+            time: Instant::now(),
+            correct: Some(mistaken),
+            key,
+            released: None,
+        });
+        for word in &mut self.words[line] {
+            word.progress.clear();
+        }
+        self.current_word = start;
+    }
+
+    /// Whether the text is split into lines, like code and paragraphs, rather than being a flat
+    /// list of words.
+    fn has_lines(&self) -> bool {
+        self.words.iter().any(|w| w.ends_paragraph)
+    }
+
+    /// Index of the first word on the same line as a word, or the word itself without lines.
+    fn line_start(&self, word: usize) -> usize {
+        if !self.has_lines() {
+            return word;
+        }
+        self.words[..word]
+            .iter()
+            .rposition(|w| w.ends_paragraph)
+            .map_or(0, |end| end + 1)
+    }
+
+    /// Truncate the progress of the current word to the length chosen by `keep`.
+    ///
+    /// Like backspace, this counts as correct only when it removes a mistake.
+    fn delete_back(&mut self, key: KeyEvent, keep: impl FnOnce(&str) -> usize) {
+        self.resume();
+
+        if self.words[self.current_word].progress.is_empty() {
+            self.last_word();
+        }
+
+        let word = &mut self.words[self.current_word];
+        if word.progress.is_empty() {
+            return;
+        }
+
+        word.events.push(TestEvent {
+            time: Instant::now(),
+            correct: Some(!word.progress_correct(&self.matching)),
+            key,
+            released: None,
+        });
+        let len = keep(&word.progress);
+        word.progress.truncate(len);
+    }
+
+    /// Whether a character appears anywhere in the test.
+    pub fn contains_char(&self, c: char) -> bool {
+        self.words.iter().any(|w| w.text.contains(c))
+    }
+
     fn last_word(&mut self) {
         if self.current_word != 0 {
             self.current_word -= 1;
@@ -130,3 +446,190 @@ impl Test {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn type_str(test: &mut Test, string: &str) {
+        for c in string.chars() {
+            test.handle_key(KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE));
+        }
+    }
+
+    #[test]
+    fn strict_tests_require_correct_words() {
+        let mut test = Test::new(vec!["ab".into(), "cd".into()]);
+        test.strict = true;
+
+        type_str(&mut test, "ax ");
+        assert_eq!(test.current_word, 0);
+
+        test.handle_key(KeyEvent::new(KeyCode::Backspace, KeyModifiers::NONE));
+        type_str(&mut test, "b ");
+        assert_eq!(test.current_word, 1);
+    }
+
+    #[test]
+    fn deletes_words_and_lines() {
+        let ctrl_w = KeyEvent::new(KeyCode::Char('w'), KeyModifiers::CONTROL);
+        let mut test = Test::new(vec!["let x = 1;\n".into(), "x".into()]);
+
+        type_str(&mut test, "let y = ");
+        test.delete_word(ctrl_w);
+        assert_eq!(test.words[0].progress, "let y ");
+        test.delete_word(ctrl_w);
+        assert_eq!(test.words[0].progress, "let ");
+        // removing a mistake is correct, removing correct text isn't
+        assert_eq!(test.words[0].events.last().unwrap().correct, Some(true));
+        test.delete_word(ctrl_w);
+        assert_eq!(test.words[0].progress, "");
+        assert_eq!(test.words[0].events.last().unwrap().correct, Some(false));
+
+        type_str(&mut test, "let x = 1; ");
+        assert_eq!(test.current_word, 1);
+        test.delete_line(KeyEvent::new(KeyCode::Char('u'), KeyModifiers::CONTROL));
+        assert_eq!(test.current_word, 0);
+        assert_eq!(test.words[0].progress, "");
+    }
+
+    #[test]
+    fn deletes_lines_across_words() {
+        let ctrl_u = KeyEvent::new(KeyCode::Char('u'), KeyModifiers::CONTROL);
+        let mut test = Test::new(vec![
+            "fn".into(),
+            "main()\n".into(),
+            "let".into(),
+            "x".into(),
+            "=".into(),
+        ]);
+
+        type_str(&mut test, "fn main() let y ");
+        assert_eq!(test.current_word, 4);
+        test.delete_line(ctrl_u);
+        assert_eq!(test.current_word, 2);
+        assert!(test.words[2..].iter().all(|w| w.progress.is_empty()));
+        assert_eq!(test.words[1].progress, "main()");
+        assert_eq!(test.words[4].events.last().unwrap().correct, Some(true));
+
+        // at the start of a line, the line before is cleared
+        test.delete_line(ctrl_u);
+        assert_eq!(test.current_word, 0);
+        assert!(test.words.iter().all(|w| w.progress.is_empty()));
+        assert_eq!(test.words[1].events.last().unwrap().correct, Some(false));
+    }
+
+    #[test]
+    fn deletes_only_the_current_word_of_word_lists() {
+        let ctrl_u = KeyEvent::new(KeyCode::Char('u'), KeyModifiers::CONTROL);
+        let mut test = Test::new(vec!["the".into(), "quick".into(), "fox".into()]);
+
+        type_str(&mut test, "the quick fo");
+        test.delete_line(ctrl_u);
+        assert_eq!(test.current_word, 2);
+        assert_eq!(test.words[2].progress, "");
+        assert_eq!(test.words[1].progress, "quick");
+
+        // with nothing left to clear, earlier words are still left alone
+        test.delete_line(ctrl_u);
+        assert_eq!(test.current_word, 2);
+        assert_eq!(test.words[0].progress, "the");
+    }
+
+    #[test]
+    fn ignores_control_characters() {
+        let mut test = Test::new(vec!["ab".into()]);
+        test.handle_key(KeyEvent::new(KeyCode::Char('a'), KeyModifiers::CONTROL));
+        test.handle_key(KeyEvent::new(KeyCode::Char('a'), KeyModifiers::ALT));
+        assert_eq!(test.words[0].progress, "");
+    }
+
+    #[test]
+    fn records_key_releases() {
+        let mut test = Test::new(vec!["ab".into()]);
+        type_str(&mut test, "a");
+        assert_eq!(test.words[0].events[0].released, None);
+
+        let mut release = KeyEvent::new(KeyCode::Char('A'), KeyModifiers::NONE);
+        release.kind = KeyEventKind::Release;
+        test.handle_key(release);
+        assert_eq!(test.words[0].progress, "a");
+        assert!(test.words[0].events[0].released.is_some());
+    }
+
+    #[test]
+    fn handles_graphemes() {
+        // decomposed input for a precomposed prompt
+        let mut test = Test::new(vec!["für".into(), "x".into()]);
+        type_str(&mut test, "fu\u{308}");
+        assert_eq!(test.words[0].progress, "fü");
+        assert!(test.words[0].events.iter().all(|e| e.correct == Some(true)));
+        assert_eq!(test.words[0].typed_len(&test.matching), "fü".len());
+
+        // emoji with a skin tone modifier are a single grapheme
+        let mut test = Test::new(vec!["a👍🏽 b".into()]);
+        type_str(&mut test, "a👍🏽");
+        assert_eq!(test.words[0].typed_len(&test.matching), "a👍🏽".len());
+        type_str(&mut test, " ");
+        assert_eq!(test.words[0].progress, "a👍🏽 ");
+        test.handle_key(KeyEvent::new(KeyCode::Backspace, KeyModifiers::NONE));
+        test.handle_key(KeyEvent::new(KeyCode::Backspace, KeyModifiers::NONE));
+        assert_eq!(test.words[0].progress, "a");
+    }
+
+    #[test]
+    fn applies_matching_policy() {
+        let mut test = Test::new(vec!["Grüße".into()]);
+        test.matching = MatchPolicy {
+            ignore_case: true,
+            transliterate: true,
+            ..Default::default()
+        };
+
+        type_str(&mut test, "gru");
+        assert_eq!(test.words[0].typed_len(&test.matching), "Grü".len());
+        type_str(&mut test, "esse");
+        assert!(test.complete);
+        assert!(test.words[0].events.iter().all(|e| e.correct == Some(true)));
+    }
+
+    #[test]
+    fn reset_keeps_words() {
+        let mut test = Test::new(vec!["ab".into(), "cd".into()]);
+        type_str(&mut test, "ab c");
+        test.pause();
+
+        test.reset();
+        assert_eq!(test.current_word, 0);
+        assert!(!test.paused());
+        assert!(test.start_time().is_none());
+        assert_eq!(test.words[1].text, "cd");
+        assert!(test.words.iter().all(|w| w.progress.is_empty()));
+    }
+
+    #[test]
+    fn pauses_never_overlap() {
+        let start = Instant::now();
+        let mut test = Test::new(vec!["ab".into()]);
+        test.pause_at(start);
+        test.resume();
+        let resumed = test.pauses[0].end.unwrap();
+        assert_eq!(test.last_activity(), None);
+
+        // an idle pause dated from before the last pause ended starts once it did
+        test.pause_at(start);
+        test.resume();
+        assert_eq!(test.pauses[1].start, resumed);
+
+        // idle time is counted from when the test resumed, not from the last keypress
+        type_str(&mut test, "a");
+        test.words[0].events[0].time = start;
+        assert_eq!(test.last_activity(), test.pauses[1].end);
+
+        let end = Instant::now();
+        assert_eq!(
+            test.active_between(start, end),
+            end - test.pauses[1].end.unwrap()
+        );
+    }
+}
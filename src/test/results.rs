@@ -1,10 +1,23 @@
-use super::Test;
+use super::{matching::MatchPolicy, Test};
+use crate::layout::{Finger, Hand, KeyId, Layout};
 
-use crossterm::event::KeyEvent;
-use std::collections::HashMap;
+use crossterm::event::{KeyCode, KeyEventKind};
+use std::collections::{BTreeMap, HashMap};
 use std::{cmp, fmt};
 
-#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+// Convert CPS to WPM (clicks per second)
+pub const WPM_PER_CPS: f64 = 12.0;
+
+// Keypresses closer together than this (in seconds) are considered impossibly fast for a human
+const IMPOSSIBLE_INTERVAL: f64 = 0.01;
+// Share of keypresses which must be impossibly fast for a test to be flagged
+const IMPOSSIBLE_SHARE: f64 = 0.5;
+// Coefficient of variation of keypress intervals under which timing is considered machine-like
+const UNIFORM_VARIATION: f64 = 0.05;
+// Keypress intervals needed before timing heuristics are applied
+const MIN_INTERVALS: usize = 20;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
 pub struct Fraction {
     pub numerator: usize,
     pub denominator: usize,
@@ -60,21 +73,177 @@ pub struct TimingData {
     // Instead of storing WPM, we store CPS (clicks per second)
     pub overall_cps: f64,
     pub per_event: Vec<f64>,
-    pub per_key: HashMap<KeyEvent, f64>,
+    pub per_key: HashMap<KeyId, f64>,
+    // Indices into `per_event` of the gaps which contained a pause
+    pub pauses: Vec<usize>,
+    // Dwell is how long keys were held down and flight is the time between letting go of one
+    // key and pressing the next. Both are only known when the terminal reports key releases.
+    pub dwell: Option<f64>,
+    pub per_key_dwell: HashMap<KeyId, f64>,
+    pub flight: Option<f64>,
+    pub per_key_flight: HashMap<KeyId, f64>,
 }
 
 pub struct AccuracyData {
     pub overall: Fraction,
-    pub per_key: HashMap<KeyEvent, Fraction>,
+    pub per_key: HashMap<KeyId, Fraction>,
+}
+
+/// Totals for a group of keys, such as those pressed by one finger.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct GroupData {
+    pub accuracy: Fraction,
+    /// Total seconds taken to press keys, and how many presses that covers.
+    pub time: (f64, usize),
+    /// Presses following a press of a different key with the same finger.
+    pub same_finger_bigrams: usize,
+}
+
+impl GroupData {
+    /// Average seconds taken to press a key.
+    pub fn latency(&self) -> Option<f64> {
+        (self.time.1 != 0).then(|| self.time.0 / self.time.1 as f64)
+    }
+}
+
+/// Results summed by the finger, hand and keyboard row which typed each character.
+#[derive(Debug, Default)]
+pub struct FingerData {
+    pub per_finger: BTreeMap<Finger, GroupData>,
+    pub per_hand: BTreeMap<Hand, GroupData>,
+    /// Keyed by layout row, counting down from the number row, or none for the space bar.
+    pub per_row: BTreeMap<Option<usize>, GroupData>,
+    /// Keyed by whether shift was held.
+    pub per_shift: BTreeMap<bool, GroupData>,
+}
+
+impl FingerData {
+    fn new(test: &Test, events: &[&super::TestEvent], layout: &Layout) -> Self {
+        let mut data = Self::default();
+        // the key and finger of the last character typed
+        let mut previous: Option<(Option<(usize, usize)>, Finger)> = None;
+
+        for (i, event) in events.iter().enumerate() {
+            // the character already says whether shift was held, so other modifiers are ignored
+            let located = match event.key.code {
+                KeyCode::Char(c) => layout.locate(c),
+                _ => None,
+            };
+            let Some((position, finger)) = located else {
+                previous = None;
+                continue;
+            };
+            let key = position.map(|p| (p.row, p.col));
+
+            let mut groups = vec![
+                data.per_finger.entry(finger).or_default(),
+                data.per_row.entry(position.map(|p| p.row)).or_default(),
+                data.per_shift
+                    .entry(position.is_some_and(|p| p.shifted))
+                    .or_default(),
+            ];
+            if let Some(hand) = finger.hand() {
+                groups.push(data.per_hand.entry(hand).or_default());
+            }
+
+            // auto-repeat says nothing about how fast or with which finger a key is pressed
+            let repeat = event.key.kind == KeyEventKind::Repeat;
+            let previous_time = i.checked_sub(1).map(|prev| events[prev].time);
+            let time = previous_time
+                .filter(|&prev| !repeat && prev <= event.time)
+                .map(|prev| test.active_between(prev, event.time).as_secs_f64());
+            let same_finger = !repeat
+                && previous.is_some_and(|(prev_key, prev_finger)| {
+                    prev_finger == finger && prev_key != key
+                });
+
+            for group in groups {
+                if let Some(correct) = event.correct {
+                    group.accuracy.denominator += 1;
+                    if correct {
+                        group.accuracy.numerator += 1;
+                    }
+                }
+                if let Some(time) = time {
+                    group.time.0 += time;
+                    group.time.1 += 1;
+                }
+                if same_finger {
+                    group.same_finger_bigrams += 1;
+                }
+            }
+
+            previous = Some((key, finger));
+        }
+
+        data
+    }
+}
+
+/// Reason to distrust a test's results.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Flag {
+    Pasted,
+    ImpossibleSpeed,
+    UniformTiming,
+}
+
+impl fmt::Display for Flag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Flag::Pasted => "text was pasted",
+            Flag::ImpossibleSpeed => "keypresses were impossibly fast",
+            Flag::UniformTiming => "keypresses were evenly timed",
+        })
+    }
+}
+
+/// Identifies the challenge a test was run from.
+#[derive(Clone)]
+pub struct ChallengeInfo {
+    pub hash: String,
+    pub target_wpm: Option<f64>,
+}
+
+/// Progress through the touch typing lessons.
+pub struct LessonInfo {
+    /// Letters unlocked so far.
+    pub keys: Vec<char>,
+    /// Letter unlocked by this test.
+    pub unlocked: Option<char>,
+    /// Letter to unlock next.
+    pub next: Option<char>,
+    pub save_error: Option<String>,
+}
+
+/// Progress through a book typed over many tests.
+pub struct BookInfo {
+    /// Index of the first word not yet typed.
+    pub position: usize,
+    /// Number of words in the book.
+    pub total: usize,
+    /// Whether this test reached the end, so the next starts over.
+    pub finished: bool,
+    pub save_error: Option<String>,
 }
 
 pub struct Results {
     pub timing: TimingData,
     pub accuracy: AccuracyData,
+    /// Whether the test was finished rather than aborted.
+    pub complete: bool,
+    pub flags: Vec<Flag>,
+    /// The matching policy the test was typed under.
+    pub matching: MatchPolicy,
+    pub challenge: Option<ChallengeInfo>,
+    pub lesson: Option<LessonInfo>,
+    pub book: Option<BookInfo>,
+    pub fingers: FingerData,
 }
 
-impl From<&Test> for Results {
-    fn from(test: &Test) -> Self {
+impl Results {
+    /// Compute a test's results, attributing keys to fingers with the layout it was typed on.
+    pub fn new(test: &Test, layout: &Layout) -> Self {
         let events: Vec<&super::TestEvent> =
             test.words.iter().flat_map(|w| w.events.iter()).collect();
 
@@ -84,30 +253,77 @@ impl From<&Test> for Results {
                     overall_cps: -1.0,
                     per_event: Vec::new(),
                     per_key: HashMap::new(),
+                    pauses: Vec::new(),
+                    dwell: None,
+                    per_key_dwell: HashMap::new(),
+                    flight: None,
+                    per_key_flight: HashMap::new(),
                 };
 
                 // map of keys to a two-tuple (total time, clicks) for counting average
-                let mut keys: HashMap<KeyEvent, (f64, usize)> = HashMap::new();
+                let mut keys: HashMap<KeyId, (f64, usize)> = HashMap::new();
 
                 for win in events.windows(2) {
-                    let event_dur = win[1]
-                        .time
-                        .checked_duration_since(win[0].time)
-                        .map(|d| d.as_secs_f64());
+                    let event_dur = win[1].time.checked_duration_since(win[0].time).map(|d| {
+                        let active = test.active_between(win[0].time, win[1].time);
+                        if active < d {
+                            timing.pauses.push(timing.per_event.len());
+                        }
+                        active.as_secs_f64()
+                    });
 
                     if let Some(event_dur) = event_dur {
                         timing.per_event.push(event_dur);
 
-                        let key = keys.entry(win[1].key).or_insert((0.0, 0));
-                        key.0 += event_dur;
-                        key.1 += 1;
+                        // auto-repeat says nothing about how fast a key is pressed
+                        if win[1].key.kind != KeyEventKind::Repeat {
+                            let key = keys.entry(layout.key_id(&win[1].key)).or_insert((0.0, 0));
+                            key.0 += event_dur;
+                            key.1 += 1;
+                        }
                     }
                 }
 
-                timing.per_key = keys
-                    .into_iter()
-                    .map(|(key, (total, count))| (key, total / count as f64))
+                timing.per_key = averages(keys);
+
+                let mut presses: Vec<&&super::TestEvent> = events
+                    .iter()
+                    .filter(|e| e.key.kind == KeyEventKind::Press)
                     .collect();
+                presses.sort_by_key(|e| e.time);
+
+                let mut dwell_keys: HashMap<KeyId, (f64, usize)> = HashMap::new();
+                for event in &presses {
+                    if let Some(released) = event.released {
+                        let dwell = test.active_between(event.time, released);
+                        let key = dwell_keys
+                            .entry(layout.key_id(&event.key))
+                            .or_insert((0.0, 0));
+                        key.0 += dwell.as_secs_f64();
+                        key.1 += 1;
+                    }
+                }
+                timing.dwell = overall_average(&dwell_keys);
+                timing.per_key_dwell = averages(dwell_keys);
+
+                let mut flight_keys: HashMap<KeyId, (f64, usize)> = HashMap::new();
+                for win in presses.windows(2) {
+                    if let Some(released) = win[0].released {
+                        // rolling over from one key to the next gives a negative flight time
+                        let flight = if released <= win[1].time {
+                            test.active_between(released, win[1].time).as_secs_f64()
+                        } else {
+                            -released.duration_since(win[1].time).as_secs_f64()
+                        };
+                        let key = flight_keys
+                            .entry(layout.key_id(&win[1].key))
+                            .or_insert((0.0, 0));
+                        key.0 += flight;
+                        key.1 += 1;
+                    }
+                }
+                timing.flight = overall_average(&flight_keys);
+                timing.per_key_flight = averages(flight_keys);
 
                 timing.overall_cps =
                     timing.per_event.len() as f64 / timing.per_event.iter().sum::<f64>();
@@ -126,7 +342,7 @@ impl From<&Test> for Results {
                     .for_each(|event| {
                         let key = acc
                             .per_key
-                            .entry(event.key)
+                            .entry(layout.key_id(&event.key))
                             .or_insert_with(|| Fraction::new(0, 0));
 
                         acc.overall.denominator += 1;
@@ -140,6 +356,254 @@ impl From<&Test> for Results {
 
                 acc
             },
+            complete: test.complete,
+            flags: flags(test, &events),
+            matching: test.matching,
+            challenge: None,
+            lesson: None,
+            book: None,
+            fingers: FingerData::new(test, &events, layout),
         }
     }
 }
+
+/// Look for signs that a test wasn't typed by hand.
+fn flags(test: &Test, events: &[&super::TestEvent]) -> Vec<Flag> {
+    let mut flags = Vec::new();
+    if test.pasted {
+        flags.push(Flag::Pasted);
+    }
+
+    // only character presses are considered, since held keys repeat at a steady rate
+    let mut presses: Vec<&&super::TestEvent> = events
+        .iter()
+        .filter(|e| e.key.kind != KeyEventKind::Repeat && matches!(e.key.code, KeyCode::Char(_)))
+        .collect();
+    presses.sort_by_key(|e| e.time);
+    let intervals: Vec<f64> = presses
+        .windows(2)
+        .map(|win| test.active_between(win[0].time, win[1].time).as_secs_f64())
+        .collect();
+
+    if intervals.len() >= MIN_INTERVALS {
+        let count = intervals.len() as f64;
+        let impossible = intervals
+            .iter()
+            .filter(|&&i| i < IMPOSSIBLE_INTERVAL)
+            .count() as f64;
+        if impossible / count >= IMPOSSIBLE_SHARE {
+            flags.push(Flag::ImpossibleSpeed);
+        }
+
+        let mean = intervals.iter().sum::<f64>() / count;
+        let variance = intervals.iter().map(|i| (i - mean).powi(2)).sum::<f64>() / count;
+        if mean > 0.0 && variance.sqrt() / mean < UNIFORM_VARIATION {
+            flags.push(Flag::UniformTiming);
+        }
+    }
+
+    flags
+}
+
+/// Average each key's (total, count) pair.
+fn averages(keys: HashMap<KeyId, (f64, usize)>) -> HashMap<KeyId, f64> {
+    keys.into_iter()
+        .map(|(key, (total, count))| (key, total / count as f64))
+        .collect()
+}
+
+/// Average across all keys' (total, count) pairs, if there are any.
+fn overall_average(keys: &HashMap<KeyId, (f64, usize)>) -> Option<f64> {
+    let (total, count) = keys
+        .values()
+        .fold((0.0, 0), |(total, count), (t, c)| (total + t, count + c));
+    (count != 0).then(|| total / count as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::{Pause, TestEvent};
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    use std::time::{Duration, Instant};
+
+    fn results(test: &Test) -> Results {
+        Results::new(test, &Layout::builtin("qwerty").unwrap().unwrap())
+    }
+
+    /// A press of a character key, with shift held for uppercase letters.
+    fn event(c: char, time: Instant, correct: bool) -> TestEvent {
+        let modifiers = if c.is_uppercase() {
+            KeyModifiers::SHIFT
+        } else {
+            KeyModifiers::NONE
+        };
+        TestEvent {
+            time,
+            key: KeyEvent::new(KeyCode::Char(c), modifiers),
+            correct: Some(correct),
+            released: None,
+        }
+    }
+
+    #[test]
+    fn measures_dwell_and_flight() {
+        let start = Instant::now();
+        let at = |millis: u64| start + Duration::from_millis(millis);
+        let held = |c, pressed, released| TestEvent {
+            released: Some(at(released)),
+            ..event(c, at(pressed), true)
+        };
+
+        let mut test = Test::new(vec!["abc".into()]);
+        // 'c' is pressed before 'b' is let go
+        test.words[0].events = vec![held('a', 0, 100), held('b', 150, 300), held('c', 250, 350)];
+
+        let timing = results(&test).timing;
+        let dwell = timing.dwell.unwrap();
+        let flight = timing.flight.unwrap();
+        assert!((dwell - 0.35 / 3.0).abs() < 1e-9);
+        assert!((flight - 0.0).abs() < 1e-9);
+
+        let c = KeyId {
+            code: KeyCode::Char('c'),
+            shifted: false,
+        };
+        assert!((timing.per_key_flight[&c] + 0.05).abs() < 1e-9);
+        assert!((timing.per_key_dwell[&c] - 0.1).abs() < 1e-9);
+    }
+
+    #[test]
+    fn flags_suspicious_tests() {
+        let start = Instant::now();
+        let typed_every = |millis: u64| {
+            let mut test = Test::new(vec!["a".repeat(30)]);
+            test.words[0].events = (0..30)
+                .map(|i| {
+                    // humans don't type perfectly evenly
+                    let jitter = (i * i * 7) % millis;
+                    event(
+                        'a',
+                        start + Duration::from_millis(i * millis + jitter),
+                        true,
+                    )
+                })
+                .collect();
+            test
+        };
+
+        assert!(results(&typed_every(150)).flags.is_empty());
+
+        let mut test = typed_every(150);
+        test.pasted = true;
+        assert_eq!(results(&test).flags, vec![Flag::Pasted]);
+
+        let mut test = typed_every(150);
+        for (i, event) in test.words[0].events.iter_mut().enumerate() {
+            event.time = start + Duration::from_millis(100 * i as u64);
+        }
+        assert_eq!(results(&test).flags, vec![Flag::UniformTiming]);
+
+        assert!(results(&typed_every(5))
+            .flags
+            .contains(&Flag::ImpossibleSpeed));
+    }
+
+    #[test]
+    fn excludes_paused_time() {
+        let start = Instant::now();
+        let at = |secs: u64| start + Duration::from_secs(secs);
+
+        let mut test = Test::new(vec!["aaa".into()]);
+        test.words[0].events = vec![
+            event('a', at(0), true),
+            event('a', at(1), true),
+            event('a', at(12), true),
+        ];
+        test.pauses.push(Pause {
+            start: at(1),
+            end: Some(at(11)),
+        });
+
+        let results = results(&test);
+        assert_eq!(results.timing.per_event, vec![1.0, 1.0]);
+        assert_eq!(results.timing.pauses, vec![1]);
+        assert_eq!(results.timing.overall_cps, 1.0);
+    }
+
+    #[test]
+    fn survives_overlapping_pauses() {
+        let start = Instant::now();
+        let at = |secs: u64| start + Duration::from_secs(secs);
+
+        // pauses recorded before they were kept apart add up to more than the time between keys
+        let mut test = Test::new(vec!["aa".into()]);
+        test.words[0].events = vec![event('a', at(0), true), event('a', at(30), true)];
+        test.pauses = vec![
+            Pause {
+                start: at(1),
+                end: Some(at(20)),
+            },
+            Pause {
+                start: at(0),
+                end: Some(at(30)),
+            },
+        ];
+
+        let fingers = results(&test).fingers;
+        assert_eq!(fingers.per_finger[&Finger::LeftPinky].time, (0.0, 1));
+    }
+
+    #[test]
+    fn sums_by_finger() {
+        let start = Instant::now();
+        let at = |millis: u64| start + Duration::from_millis(millis);
+
+        let mut test = Test::new(vec!["deed".into()]);
+        // 'e' after 'd' is a same-finger bigram, but 'e' after 'e' is the same key
+        test.words[0].events = vec![
+            event('D', at(0), false),
+            event('e', at(100), true),
+            event('e', at(300), true),
+            event('d', at(400), true),
+        ];
+
+        let fingers = results(&test).fingers;
+        let middle = fingers.per_finger[&Finger::LeftMiddle];
+        assert_eq!(middle.accuracy, Fraction::new(3, 4));
+        assert_eq!(middle.same_finger_bigrams, 2);
+        assert!((middle.latency().unwrap() - 0.4 / 3.0).abs() < 1e-9);
+        assert_eq!(fingers.per_hand[&Hand::Left].accuracy, Fraction::new(3, 4));
+        assert_eq!(fingers.per_row[&Some(1)].accuracy, Fraction::new(2, 2));
+        assert_eq!(fingers.per_row[&Some(2)].accuracy, Fraction::new(1, 2));
+        assert!(!fingers.per_hand.contains_key(&Hand::Right));
+        assert_eq!(fingers.per_shift[&true].accuracy, Fraction::new(0, 1));
+    }
+
+    #[test]
+    fn identifies_keys_across_modifiers() {
+        let start = Instant::now();
+        // terminals don't always report shift, or tell repeats apart from presses
+        let mut unshifted = event('A', start, false);
+        unshifted.key.modifiers = KeyModifiers::NONE;
+        let mut repeat = event('A', start, true);
+        repeat.key.kind = KeyEventKind::Repeat;
+
+        let mut test = Test::new(vec!["aAAA".into()]);
+        test.words[0].events = vec![
+            event('a', start, true),
+            event('A', start, true),
+            unshifted,
+            repeat,
+        ];
+
+        let per_key = results(&test).accuracy.per_key;
+        let key = |shifted| KeyId {
+            code: KeyCode::Char('a'),
+            shifted,
+        };
+        assert_eq!(per_key.len(), 2);
+        assert_eq!(per_key[&key(false)], Fraction::new(1, 1));
+        assert_eq!(per_key[&key(true)], Fraction::new(2, 3));
+    }
+}
@@ -1,25 +1,48 @@
 use crate::config::Theme;
+use crate::keys::{KeyBindings, KeyChord};
+use crate::lesson;
+use crate::layout::{Finger, KeyId, Layout as KeyboardLayout, Position};
 
 use super::test::{results, Test};
 
 use crossterm::event::KeyCode;
-use crossterm::event::KeyEvent;
-use results::Fraction;
-use std::iter;
+use results::{Fraction, PartialResults, WPM_PER_CPS};
+use std::{collections::HashMap, iter};
+use unicode_segmentation::UnicodeSegmentation;
 use ratatui::{
     buffer::Buffer,
-    layout::{Constraint, Direction, Layout, Rect},
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::Style,
     symbols::Marker,
     text::{Span, Line, Text},
-    widgets::{Axis, Block, BorderType, Borders, Chart, Dataset, GraphType, Paragraph, Widget},
+    widgets::{
+        Axis, Block, BorderType, Borders, Chart, Clear, Dataset, GraphType, Paragraph, Row, Table,
+        Widget,
+    },
 };
 
-// Convert CPS to WPM (clicks per second)
-const WPM_PER_CPS: f64 = 12.0;
-
 // Width of the moving average window for the WPM chart
 const WPM_SMA_WIDTH: usize = 10;
 
+// Indentation of each keyboard row, in cells, approximating a staggered keyboard
+const KEYBOARD_ROW_OFFSETS: [usize; 4] = [0, 6, 7, 9];
+// Width of the space bar, in keys
+const KEYBOARD_SPACE_WIDTH: usize = 6;
+// Height of the keyboard block: four rows of keys, the space bar, a hint line and borders
+const KEYBOARD_HEIGHT: u16 = 8;
+
+// Height of the fingers block: a header, a line for each finger and borders
+const FINGERS_HEIGHT: u16 = 12;
+
+// Names of the layout rows, counting down from the number row
+const ROW_NAMES: [&str; 4] = ["number row", "top row", "home row", "bottom row"];
+
+// Error rates from which a key is colored as fair and poor on the accuracy heatmap
+const HEATMAP_ERROR_RATES: [f64; 2] = [0.05, 0.15];
+// Multiples of the average keypress time from which a key is colored as fair and poor on the
+// latency heatmap
+const HEATMAP_LATENCY_RATIOS: [f64; 2] = [1.0, 1.5];
+
 #[derive(Clone)]
 struct SizedBlock<'a> {
     block: Block<'a>,
@@ -75,27 +98,65 @@ impl Theme {
     }
 }
 
-impl ThemedWidget for &Test {
+/// A test, along with the keyboard to show hints on and the book it's from, if any.
+pub struct TestScreen<'a> {
+    pub test: &'a Test,
+    pub keyboard: Option<&'a KeyboardLayout>,
+    /// Where the test starts in the book it's from, and how many words the book has.
+    pub book: Option<(usize, usize)>,
+}
+
+impl ThemedWidget for TestScreen<'_> {
     fn render(self, area: Rect, buf: &mut Buffer, theme: &Theme) {
+        let Self {
+            test,
+            keyboard,
+            book,
+        } = self;
         buf.set_style(area, theme.default);
 
         // Chunks
         let chunks = Layout::default()
             .direction(Direction::Vertical)
-            .constraints([Constraint::Length(3), Constraint::Length(6)])
+            .constraints([
+                Constraint::Length(3),
+                Constraint::Length(6),
+                Constraint::Length(if keyboard.is_some() {
+                    KEYBOARD_HEIGHT
+                } else {
+                    0
+                }),
+                Constraint::Min(0),
+            ])
             .split(area);
 
         // Sections
         let input = SizedBlock {
             block: Block::default()
-                .title(Line::from(vec![Span::styled("Input", theme.title)]))
+                .title(Line::from(vec![
+                    Span::styled("Input", theme.title),
+                    Span::styled(
+                        match test.time_remaining() {
+                            Some(remaining) => format!(" ({}s)", remaining.as_secs_f64().ceil()),
+                            None => format!(" ({})", test.progress()),
+                        },
+                        theme.input_progress,
+                    ),
+                    Span::styled(
+                        book.map_or(String::new(), |(position, total)| {
+                            let position = position + test.current_word;
+                            format!(" (book {:.1}%)", position as f64 / total as f64 * 100.0)
+                        }),
+                        theme.input_progress,
+                    ),
+                ]))
                 .borders(Borders::ALL)
                 .border_type(BorderType::Rounded)
                 .border_style(theme.input_border),
             area: chunks[0],
         };
         input.draw_inner(
-            &Line::from(self.words[self.current_word].progress.clone()),
+            &Line::from(test.words[test.current_word].progress.clone()),
             buf,
         );
         input.render(buf);
@@ -103,10 +164,10 @@ impl ThemedWidget for &Test {
         let target_lines: Vec<Line> = {
             let words = iter::empty::<Vec<Span>>()
                 // already typed words
-                .chain(self.words[..self.current_word].iter().map(|w| {
+                .chain(test.words[..test.current_word].iter().map(|w| {
                     vec![Span::styled(
                         w.text.clone() + " ",
-                        if w.progress == w.text {
+                        if w.typed_correctly(&test.matching) {
                             theme.prompt_correct
                         } else {
                             theme.prompt_incorrect
@@ -115,24 +176,11 @@ impl ThemedWidget for &Test {
                 }))
                 // current word
                 .chain({
-                    let progress_ind = self.words[self.current_word]
-                        .progress
-                        .len()
-                        .min(self.words[self.current_word].text.len());
-
-                    let correct = self.words[self.current_word]
-                        .text
-                        .starts_with(&self.words[self.current_word].progress[..]);
-
-                    let (typed, untyped) =
-                        self.words[self.current_word]
-                            .text
-                            .split_at(ceil_char_boundary(
-                                &self.words[self.current_word].text,
-                                progress_ind,
-                            ));
-
-                    let mut remaining = untyped.chars().chain(iter::once(' '));
+                    let word = &test.words[test.current_word];
+                    let correct = word.progress_correct(&test.matching);
+                    let (typed, untyped) = word.text.split_at(word.typed_len(&test.matching));
+
+                    let mut remaining = untyped.graphemes(true).chain(iter::once(" "));
                     let cursor = remaining.next().unwrap();
 
                     iter::once(vec![
@@ -145,7 +193,7 @@ impl ThemedWidget for &Test {
                             },
                         ),
                         Span::styled(
-                            cursor.to_string(),
+                            cursor,
                             theme.prompt_current_untyped.patch(theme.prompt_cursor),
                         ),
                         Span::styled(remaining.collect::<String>(), theme.prompt_current_untyped),
@@ -153,7 +201,7 @@ impl ThemedWidget for &Test {
                 })
                 // remaining words
                 .chain(
-                    self.words[self.current_word + 1..]
+                    test.words[test.current_word + 1..]
                         .iter()
                         .map(|w| vec![Span::styled(w.text.clone() + " ", theme.prompt_untyped)]),
                 );
@@ -161,7 +209,7 @@ impl ThemedWidget for &Test {
             let mut lines: Vec<Line> = Vec::new();
             let mut current_line: Vec<Span> = Vec::new();
             let mut current_width = 0;
-            for word in words {
+            for (word, ends_paragraph) in words.zip(test.words.iter().map(|w| w.ends_paragraph)) {
                 let word_width: usize = word.iter().map(|s| s.width()).sum();
 
                 if current_width + word_width > chunks[1].width as usize - 2 {
@@ -173,6 +221,13 @@ impl ThemedWidget for &Test {
 
                 current_line.extend(word);
                 current_width += word_width;
+
+                // paragraphs start on a new line
+                if ends_paragraph {
+                    lines.push(Line::from(current_line.clone()));
+                    current_line.clear();
+                    current_width = 0;
+                }
             }
             lines.push(Line::from(current_line));
 
@@ -180,17 +235,129 @@ impl ThemedWidget for &Test {
         };
         let target = Paragraph::new(target_lines).block(
             Block::default()
-                .title(Span::styled("Prompt", theme.title))
+                .title(if test.matching.is_exact() {
+                    Line::from(Span::styled("Prompt", theme.title))
+                } else {
+                    Line::from(vec![
+                        Span::styled("Prompt", theme.title),
+                        Span::styled(format!(" ({})", test.matching), theme.input_progress),
+                    ])
+                })
                 .borders(Borders::ALL)
                 .border_type(BorderType::Rounded)
                 .border_style(theme.prompt_border),
         );
         target.render(chunks[1], buf);
+
+        if test.paused() {
+            let message = "Paused. Press any key to resume.";
+            let width = (message.len() as u16 + 4).min(chunks[1].width);
+            let overlay_area = Rect {
+                x: chunks[1].x + (chunks[1].width - width) / 2,
+                y: chunks[1].y + chunks[1].height.saturating_sub(3) / 2,
+                width,
+                height: 3.min(chunks[1].height),
+            };
+
+            Clear.render(overlay_area, buf);
+            Paragraph::new(Span::styled(message, theme.paused_overlay))
+                .alignment(Alignment::Center)
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .border_type(BorderType::Rounded)
+                        .border_style(theme.paused_overlay_border),
+                )
+                .render(overlay_area, buf);
+        }
+
+        if let Some(layout) = keyboard {
+            let next = test.next_char().and_then(|c| layout.locate(c));
+            let style_of = |position: Option<Position>, finger: Finger| match next {
+                Some((next_position, next_finger)) => {
+                    let same_key = match (position, next_position) {
+                        (Some(a), Some(b)) => a.row == b.row && a.col == b.col,
+                        (None, None) => true,
+                        _ => false,
+                    };
+                    if same_key {
+                        theme.keyboard_next
+                    } else if finger == next_finger {
+                        theme.keyboard_finger
+                    } else {
+                        theme.keyboard_key
+                    }
+                }
+                None => theme.keyboard_key,
+            };
+
+            let mut lines = keyboard_lines(layout, style_of);
+            lines.push(Line::from(Span::styled(
+                match next {
+                    Some((position, finger)) => format!(
+                        "{}{}",
+                        finger,
+                        if position.is_some_and(|p| p.shifted) {
+                            ", holding shift"
+                        } else {
+                            ""
+                        }
+                    ),
+                    None => String::new(),
+                },
+                theme.keyboard_hint,
+            )));
+
+            Paragraph::new(lines)
+                .block(
+                    Block::default()
+                        .title(Span::styled("Keyboard", theme.title))
+                        .borders(Borders::ALL)
+                        .border_type(BorderType::Rounded)
+                        .border_style(theme.keyboard_border),
+                )
+                .render(chunks[2], buf);
+        }
     }
 }
 
-impl ThemedWidget for &results::Results {
+/// Which per-key statistic the results keyboard is colored by.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Heatmap {
+    #[default]
+    Accuracy,
+    Latency,
+}
+
+impl Heatmap {
+    pub fn toggled(self) -> Self {
+        match self {
+            Heatmap::Accuracy => Heatmap::Latency,
+            Heatmap::Latency => Heatmap::Accuracy,
+        }
+    }
+}
+
+/// The results of a test, along with the keys for leaving them and the keyboard to draw them on.
+pub struct ResultsScreen<'a> {
+    pub results: &'a results::Results,
+    pub keys: &'a KeyBindings,
+    pub keyboard: &'a KeyboardLayout,
+    pub heatmap: Heatmap,
+    /// Whether to show results by finger rather than by key.
+    pub show_fingers: bool,
+}
+
+impl ThemedWidget for ResultsScreen<'_> {
     fn render(self, area: Rect, buf: &mut Buffer, theme: &Theme) {
+        let Self {
+            results,
+            keys,
+            keyboard,
+            heatmap,
+            show_fingers,
+        } = self;
+
         buf.set_style(area, theme.default);
 
         // Chunks
@@ -201,15 +368,34 @@ impl ThemedWidget for &results::Results {
         let res_chunks = Layout::default()
             .direction(Direction::Vertical)
             .margin(1) // Graph looks tremendously better with just a little margin
-            .constraints([Constraint::Ratio(1, 3), Constraint::Ratio(2, 3)])
+            .constraints([
+                Constraint::Ratio(1, 3),
+                Constraint::Length(if show_fingers {
+                    FINGERS_HEIGHT
+                } else {
+                    KEYBOARD_HEIGHT
+                }),
+                Constraint::Min(1),
+            ])
             .split(chunks[0]);
         let info_chunks = Layout::default()
             .direction(Direction::Horizontal)
             .constraints([Constraint::Ratio(1, 2), Constraint::Ratio(1, 2)])
             .split(res_chunks[0]);
 
+        let key_names = |chords: &[KeyChord]| {
+            chords
+                .iter()
+                .map(|chord| format!("'{}'", chord))
+                .collect::<Vec<_>>()
+                .join("/")
+        };
         let exit = Span::styled(
-            "Press 'q' to quit or 'r' for another test.",
+            format!(
+                "Press {} to quit or {} for another test.",
+                key_names(&keys.results_quit),
+                key_names(&keys.results_restart)
+            ),
             theme.results_restart_prompt,
         );
         buf.set_span(chunks[1].x, chunks[1].y, &exit, chunks[1].width);
@@ -219,28 +405,103 @@ impl ThemedWidget for &results::Results {
         overview_text.extend([
             Line::from(format!(
                 "Adjusted WPM: {:.1}",
-                self.timing.overall_cps * WPM_PER_CPS * f64::from(self.accuracy.overall)
+                results.timing.overall_cps * WPM_PER_CPS * f64::from(results.accuracy.overall)
             )),
             Line::from(format!(
                 "Accuracy: {:.1}%",
-                f64::from(self.accuracy.overall) * 100f64
+                f64::from(results.accuracy.overall) * 100f64
             )),
             Line::from(format!(
                 "Raw WPM: {:.1}",
-                self.timing.overall_cps * WPM_PER_CPS
+                results.timing.overall_cps * WPM_PER_CPS
             )),
-            Line::from(format!("Correct Keypresses: {}", self.accuracy.overall)),
+            Line::from(format!("Correct Keypresses: {}", results.accuracy.overall)),
         ]);
+        if !results.matching.is_exact() {
+            overview_text.extend([Line::from(format!("Matching: {}", results.matching))]);
+        }
+        overview_text.extend(results.flags.iter().map(|flag| {
+            Line::from(Span::styled(
+                format!("Flagged: {}", flag),
+                theme.results_flagged,
+            ))
+        }));
+        if let (Some(dwell), Some(flight)) = (results.timing.dwell, results.timing.flight) {
+            overview_text.extend([Line::from(format!(
+                "Dwell/Flight: {:.0}ms/{:.0}ms",
+                dwell * 1000.0,
+                flight * 1000.0
+            ))]);
+        }
+        if let Some(challenge) = &results.challenge {
+            overview_text.extend([Line::from(format!("Challenge: {:.16}", challenge.hash))]);
+            if let Some(target) = challenge.target_wpm {
+                let wpm =
+                    results.timing.overall_cps * WPM_PER_CPS * f64::from(results.accuracy.overall);
+                overview_text.extend([Line::from(format!(
+                    "Target WPM: {:.1} ({})",
+                    target,
+                    if wpm >= target { "met" } else { "missed" }
+                ))]);
+            }
+        }
+        if let Some(lesson) = &results.lesson {
+            overview_text.extend([Line::from(format!(
+                "Lesson Keys: {}",
+                lesson.keys.iter().collect::<String>()
+            ))]);
+            overview_text.extend([Line::from(match (lesson.unlocked, lesson.next) {
+                (Some(key), _) => format!("Unlocked: {}", key),
+                (None, Some(key)) => format!(
+                    "Next Key: {} at {:.0}% accuracy and {:.0} WPM",
+                    key,
+                    lesson::UNLOCK_ACCURACY * 100.0,
+                    lesson::UNLOCK_WPM
+                ),
+                (None, None) => "All keys unlocked".to_string(),
+            })]);
+            if let Some(err) = &lesson.save_error {
+                overview_text.extend([Line::from(Span::styled(
+                    format!("Couldn't save progress: {}", err),
+                    theme.results_flagged,
+                ))]);
+            }
+        }
+        if let Some(book) = &results.book {
+            overview_text.extend([Line::from(if book.finished {
+                "Book: finished, starting over".to_string()
+            } else {
+                format!(
+                    "Book: {}/{} words ({:.1}%)",
+                    book.position,
+                    book.total,
+                    book.position as f64 / book.total as f64 * 100.0
+                )
+            })]);
+            if let Some(err) = &book.save_error {
+                overview_text.extend([Line::from(Span::styled(
+                    format!("Couldn't save bookmark: {}", err),
+                    theme.results_flagged,
+                ))]);
+            }
+        }
         let overview = Paragraph::new(overview_text).block(
             Block::default()
-                .title(Span::styled("Overview", theme.title))
+                .title(Span::styled(
+                    if results.complete {
+                        "Overview"
+                    } else {
+                        "Overview (incomplete)"
+                    },
+                    theme.title,
+                ))
                 .borders(Borders::ALL)
                 .border_type(BorderType::Rounded)
                 .border_style(theme.results_overview_border),
         );
         overview.render(info_chunks[0], buf);
 
-        let mut worst_keys: Vec<(&KeyEvent, &Fraction)> = self
+        let mut worst_keys: Vec<(&KeyId, &Fraction)> = results
             .accuracy
             .per_key
             .iter()
@@ -249,23 +510,13 @@ impl ThemedWidget for &results::Results {
         worst_keys.sort_unstable_by_key(|x| x.1);
 
         let mut worst_text = Text::styled("", theme.results_worst_keys);
-        worst_text.extend(
-            worst_keys
-                .iter()
-                .take(5)
-                .filter_map(|(key, acc)| {
-                    if let KeyCode::Char(character) = key.code {
-                        Some(format!(
-                            "- {} at {:.1}% accuracy",
-                            character,
-                            f64::from(**acc) * 100.0,
-                        ))
-                    } else {
-                        None
-                    }
-                })
-                .map(Line::from),
-        );
+        worst_text.extend(worst_keys.iter().take(5).map(|(key, acc)| {
+            Line::from(format!(
+                "- {} at {:.1}% accuracy",
+                key,
+                f64::from(**acc) * 100.0,
+            ))
+        }));
         let worst = Paragraph::new(worst_text).block(
             Block::default()
                 .title(Span::styled("Worst Keys", theme.title))
@@ -275,7 +526,102 @@ impl ThemedWidget for &results::Results {
         );
         worst.render(info_chunks[1], buf);
 
-        let wpm_sma: Vec<(f64, f64)> = self
+        if show_fingers {
+            let title = vec![
+                Span::styled("Fingers", theme.title),
+                Span::styled(
+                    format!(" (press {} for keys)", key_names(&keys.results_fingers)),
+                    theme.results_restart_prompt,
+                ),
+            ];
+            render_fingers(&results.fingers, title, res_chunks[1], buf, theme);
+        } else {
+            // Each key is colored by how bad it was, from 0 (good) to 2 (poor)
+            let mut spots: HashMap<Option<(usize, usize)>, (f64, usize)> = HashMap::new();
+            let spot = |key: &KeyId| match key.code {
+                KeyCode::Char(' ') => Some(None),
+                KeyCode::Char(c) => keyboard.position(c).map(|p| Some((p.row, p.col))),
+                _ => None,
+            };
+            let (title, thresholds, legend) = match heatmap {
+                Heatmap::Accuracy => {
+                    for (key, fraction) in &results.accuracy.per_key {
+                        if let Some(spot) = spot(key) {
+                            let entry = spots.entry(spot).or_insert((0.0, 0));
+                            entry.0 += (fraction.denominator - fraction.numerator) as f64;
+                            entry.1 += fraction.denominator;
+                        }
+                    }
+                    (
+                        "Key Accuracy",
+                        HEATMAP_ERROR_RATES,
+                        ["under 5% errors", "under 15% errors", "more errors"],
+                    )
+                }
+                Heatmap::Latency => {
+                    let average = 1.0 / results.timing.overall_cps;
+                    for (key, time) in &results.timing.per_key {
+                        if let Some(spot) = spot(key) {
+                            let entry = spots.entry(spot).or_insert((0.0, 0));
+                            entry.0 += time / average;
+                            entry.1 += 1;
+                        }
+                    }
+                    (
+                        "Key Speed",
+                        HEATMAP_LATENCY_RATIOS,
+                        ["faster than average", "up to 1.5x slower", "even slower"],
+                    )
+                }
+            };
+            let styles = [
+                theme.results_heatmap_good,
+                theme.results_heatmap_fair,
+                theme.results_heatmap_poor,
+            ];
+            let style_of = |position: Option<Position>, _| {
+                let spot = position.map(|p| (p.row, p.col));
+                match spots.get(&spot) {
+                    Some(&(total, count)) if count > 0 => {
+                        let value = total / count as f64;
+                        styles[thresholds.iter().filter(|&&t| value >= t).count()]
+                    }
+                    _ => theme.results_heatmap_untyped,
+                }
+            };
+
+            let mut heatmap_lines = keyboard_lines(keyboard, style_of);
+            heatmap_lines.push(Line::from(
+                legend
+                    .iter()
+                    .zip(styles)
+                    .flat_map(|(text, style)| {
+                        [Span::styled(format!(" {} ", text), style), Span::raw(" ")]
+                    })
+                    .collect::<Vec<_>>(),
+            ));
+            Paragraph::new(heatmap_lines)
+                .block(
+                    Block::default()
+                        .title(vec![
+                            Span::styled(title, theme.title),
+                            Span::styled(
+                                format!(
+                                    " (press {} to switch, {} for fingers)",
+                                    key_names(&keys.results_heatmap),
+                                    key_names(&keys.results_fingers)
+                                ),
+                                theme.results_restart_prompt,
+                            ),
+                        ])
+                        .borders(Borders::ALL)
+                        .border_type(BorderType::Rounded)
+                        .border_style(theme.results_heatmap_border),
+                )
+                .render(res_chunks[1], buf);
+        }
+
+        let wpm_sma: Vec<(f64, f64)> = results
             .timing
             .per_event
             .windows(WPM_SMA_WIDTH)
@@ -297,19 +643,36 @@ impl ThemedWidget for &results::Results {
             .map(|(_, x)| x)
             .fold(f64::NEG_INFINITY, |a, &b| a.max(b));
 
-        let wpm_datasets = vec![Dataset::default()
+        let pause_markers: Vec<[(f64, f64); 2]> = results
+            .timing
+            .pauses
+            .iter()
+            .map(|&i| {
+                let x = (i + 1) as f64;
+                [(x, wpm_sma_min), (x, wpm_sma_max)]
+            })
+            .collect();
+
+        let mut wpm_datasets = vec![Dataset::default()
             .name("WPM")
             .marker(Marker::Braille)
             .graph_type(GraphType::Line)
             .style(theme.results_chart)
             .data(&wpm_sma)];
+        wpm_datasets.extend(pause_markers.iter().map(|marker| {
+            Dataset::default()
+                .marker(Marker::Braille)
+                .graph_type(GraphType::Line)
+                .style(theme.results_chart_pause)
+                .data(marker)
+        }));
 
         let wpm_chart = Chart::new(wpm_datasets)
             .block(Block::default().title(vec![Span::styled("Chart", theme.title)]))
             .x_axis(
                 Axis::default()
                     .title(Span::styled("Keypresses", theme.results_chart_x))
-                    .bounds([0.0, self.timing.per_event.len() as f64]),
+                    .bounds([0.0, results.timing.per_event.len() as f64]),
             )
             .y_axis(
                 Axis::default()
@@ -325,15 +688,132 @@ impl ThemedWidget for &results::Results {
                             .collect(),
                     ),
             );
-        wpm_chart.render(res_chunks[1], buf);
+        wpm_chart.render(res_chunks[2], buf);
     }
 }
 
-// FIXME: replace with `str::ceil_char_boundary` when stable
-fn ceil_char_boundary(string: &str, index: usize) -> usize {
-    if string.is_char_boundary(index) {
-        index
-    } else {
-        ceil_char_boundary(string, index + 1)
-    }
+/// Draw a keyboard layout, styling each key by its position and finger.
+///
+/// The space bar is drawn below the other keys, with no position.
+fn keyboard_lines<'a>(
+    layout: &KeyboardLayout,
+    style_of: impl Fn(Option<Position>, Finger) -> Style,
+) -> Vec<Line<'a>> {
+    let mut lines: Vec<Line> = layout
+        .rows
+        .iter()
+        .enumerate()
+        .map(|(row, keys)| {
+            let offset = KEYBOARD_ROW_OFFSETS.get(row).copied().unwrap_or(0);
+            let mut spans = vec![Span::raw(" ".repeat(offset))];
+            for (col, key) in keys.chars().enumerate() {
+                let position = Position {
+                    row,
+                    col,
+                    shifted: false,
+                };
+                spans.push(Span::styled(
+                    format!(" {} ", key),
+                    style_of(Some(position), layout.finger(position)),
+                ));
+                spans.push(Span::raw(" "));
+            }
+            Line::from(spans)
+        })
+        .collect();
+
+    let space_offset = KEYBOARD_ROW_OFFSETS[3] + 2 * 4;
+    lines.push(Line::from(vec![
+        Span::raw(" ".repeat(space_offset)),
+        Span::styled(
+            format!("{:^width$}", "space", width = KEYBOARD_SPACE_WIDTH * 4 - 1),
+            style_of(None, Finger::Thumb),
+        ),
+    ]));
+
+    lines
+}
+
+/// Draw tables of results by finger, and by hand and keyboard row.
+fn render_fingers(
+    fingers: &results::FingerData,
+    title: Vec<Span>,
+    area: Rect,
+    buf: &mut Buffer,
+    theme: &Theme,
+) {
+    let block = Block::default()
+        .title(title)
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .border_style(theme.results_fingers_border);
+    let inner = block.inner(area);
+    block.render(area, buf);
+
+    let row = |name: String, group: &results::GroupData| {
+        Row::new(vec![
+            name,
+            if group.accuracy.denominator == 0 {
+                "-".to_string()
+            } else {
+                format!("{:.0}%", f64::from(group.accuracy) * 100.0)
+            },
+            group
+                .latency()
+                .map_or("-".to_string(), |l| format!("{:.0}ms", l * 1000.0)),
+            group.same_finger_bigrams.to_string(),
+        ])
+    };
+    let table = |rows: Vec<Row<'static>>| {
+        Table::new(rows)
+            .style(theme.results_fingers)
+            // SFB is the number of same-finger bigrams
+            .header(Row::new(vec!["", "Acc", "Time", "SFB"]).style(theme.title))
+            .widths(&[
+                Constraint::Length(19),
+                Constraint::Length(6),
+                Constraint::Length(6),
+                Constraint::Length(4),
+            ])
+    };
+
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Ratio(1, 2), Constraint::Ratio(1, 2)])
+        .split(inner);
+
+    table(
+        fingers
+            .per_finger
+            .iter()
+            .map(|(finger, group)| row(finger.to_string(), group))
+            .collect(),
+    )
+    .render(columns[0], buf);
+
+    table(
+        fingers
+            .per_hand
+            .iter()
+            .map(|(hand, group)| row(hand.to_string(), group))
+            .chain(fingers.per_shift.iter().map(|(&shifted, group)| {
+                let name = if shifted {
+                    "shifted keys"
+                } else {
+                    "unshifted keys"
+                };
+                row(name.to_string(), group)
+            }))
+            .chain(fingers.per_row.iter().map(|(r, group)| {
+                let name = match r {
+                    None => "space bar".to_string(),
+                    Some(r) => ROW_NAMES
+                        .get(*r)
+                        .map_or_else(|| format!("row {}", r + 1), |name| name.to_string()),
+                };
+                row(name, group)
+            }))
+            .collect(),
+    )
+    .render(columns[1], buf);
 }